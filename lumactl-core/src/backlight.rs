@@ -0,0 +1,104 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use eyre::{Context, Result};
+use tracing::instrument;
+
+use crate::{brightness_control::connector_name, display_info::is_internal_connector};
+
+const SYS_BACKLIGHT_ROOT: &str = "/sys/class/backlight/";
+/// Backlight of Apple Silicon laptops, which is registered by apple_dcp and it is not related
+/// to the drm connector
+const APPLE_PANEL_BACKLIGHT: &str = "apple-panel-bl";
+
+/// Path of the backlight device with the given name, e.g. intel_backlight
+pub fn backlight_path(name: &str) -> PathBuf {
+    Path::new(SYS_BACKLIGHT_ROOT).join(name)
+}
+
+/// Backlight devices of the drm connector, the most appropriate first.
+/// Backlights registered by the GPU driver are children of the connector, while the firmware
+/// and vendor ones (acpi_video, nvidia_0) are children of the GPU and only apply to
+/// internal panels
+pub fn connector_backlights(connector: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(SYS_BACKLIGHT_ROOT) else {
+        return Vec::new();
+    };
+    let connector = connector.canonicalize().ok();
+    // The connector device is the drm card, whose device is the GPU
+    let gpu = connector
+        .as_ref()
+        .and_then(|connector| connector.join("device/device").canonicalize().ok());
+    let internal = connector
+        .as_deref()
+        .is_some_and(|connector| is_internal_connector(&connector_name(connector)));
+    let mut backlights = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let backlight = entry.path();
+            let parent = backlight.join("device").canonicalize().ok();
+            let priority = if parent.is_some() && parent == connector {
+                0
+            } else if internal && parent.is_some() && parent == gpu {
+                1 + type_priority(&backlight)
+            } else if internal && entry.file_name() == APPLE_PANEL_BACKLIGHT {
+                4
+            } else {
+                return None;
+            };
+            Some((priority, backlight))
+        })
+        .collect::<Vec<_>>();
+    backlights.sort();
+    backlights
+        .into_iter()
+        .map(|(_, backlight)| backlight)
+        .collect()
+}
+
+/// The kernel suggests preferring firmware over platform over raw backlights
+fn type_priority(backlight: &Path) -> u8 {
+    match fs::read_to_string(backlight.join("type"))
+        .unwrap_or_default()
+        .trim()
+    {
+        "firmware" => 0,
+        "platform" => 1,
+        _ => 2,
+    }
+}
+
+/// Current and maximum brightness of the backlight device
+#[instrument(level = "debug")]
+pub fn backlight_brightness(path: &Path) -> Result<(u32, u32)> {
+    let br_path = Path::new(path).join("brightness");
+    let br =
+        parse_path(br_path).with_context(|| format!("failed to read brightness for {:?}", path))?;
+    let max_br_path = Path::new(path).join("max_brightness");
+    let max_br = parse_path(max_br_path)
+        .with_context(|| format!("failed to read max_brightness for {:?}", path))?;
+    Ok((br, max_br))
+}
+
+/// Write the raw brightness value of the backlight device
+#[instrument(level = "debug")]
+pub fn set_backlight_brightness(path: &Path, new_br: u32) -> Result<(), eyre::Error> {
+    let br_path = Path::new(path).join("brightness");
+    std::fs::write(&br_path, new_br.to_string()).context("failed to write brightness")
+}
+
+/// Write one of the FB_BLANK_* values to bl_power
+#[instrument(level = "debug")]
+pub fn set_backlight_power(path: &Path, value: u32) -> Result<()> {
+    let power_path = path.join("bl_power");
+    std::fs::write(&power_path, value.to_string()).context("failed to write bl_power")
+}
+
+fn parse_path(path: std::path::PathBuf) -> Result<u32> {
+    std::fs::read_to_string(&path)?
+        .trim()
+        .parse()
+        .context("failed to parse brightness")
+}
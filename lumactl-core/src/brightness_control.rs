@@ -0,0 +1,693 @@
+use std::{
+    fmt, fs,
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, Instant},
+};
+
+use eyre::{bail, ensure, eyre, Context, Result};
+use tracing::{debug, instrument};
+
+use crate::{
+    backlight::{
+        backlight_brightness, backlight_path, connector_backlights, set_backlight_brightness,
+        set_backlight_power,
+    },
+    cache::ControlCache,
+    config::{Backend, Config, DisplayConfig},
+    ddc::{
+        ddc_feature, ddc_temperature, find_ddc_display, get_ddc_display, open_ddc_display,
+        set_ddc_feature, set_ddc_temperature, DdcDisplay, CONTRAST_VCP, POWER_MODE_VCP,
+    },
+    display_info::DisplayInfo,
+    gsd_power,
+    mock::{self, mock_brightness, set_mock_brightness},
+    powerdevil,
+};
+
+/// Range the new brightness is kept in, as absolute values or percentages
+#[derive(Default)]
+pub struct Limits<'a> {
+    /// The new brightness is never lower than this
+    pub min: Option<&'a str>,
+    /// The lowest of them applies, over the minimum as well
+    pub max: Vec<&'a str>,
+}
+
+impl<'a> Limits<'a> {
+    /// Limits of the display from the config, along with the night maximum if any
+    pub fn new(display_config: Option<&'a DisplayConfig>, night_max: Option<&'a str>) -> Self {
+        Self {
+            min: display_config.and_then(|display_config| display_config.min.as_deref()),
+            max: night_max
+                .into_iter()
+                .chain(display_config.and_then(|display_config| display_config.max.as_deref()))
+                .collect(),
+        }
+    }
+}
+
+/// Directory of the drm connectors
+pub(crate) const SYS_DRM_ROOT: &str = "/sys/class/drm/";
+/// Highest i2c bus index searched inside the drm connector directory
+pub(crate) const MAX_I2C_INDEX: u32 = 20;
+
+/// Power state of a display
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+pub enum PowerState {
+    /// Turn the display on
+    On,
+    /// Low power mode, quick to resume
+    Standby,
+    /// Turn the display off
+    Off,
+}
+
+/// Setting of a display that is read and changed the same way as the brightness
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Feature {
+    /// Available for every kind of control
+    Brightness,
+    /// Only available over DDC/CI
+    Contrast,
+}
+
+impl Feature {
+    /// VCP feature code of the setting on the DDC/CI display
+    fn vcp(self, ddc: &DdcDisplay) -> u8 {
+        match self {
+            Feature::Brightness => ddc.brightness_vcp,
+            Feature::Contrast => CONTRAST_VCP,
+        }
+    }
+}
+
+impl fmt::Display for Feature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Feature::Brightness => "brightness",
+            Feature::Contrast => "contrast",
+        })
+    }
+}
+
+/// The way the brightness of a display is controlled
+pub enum BrightnessControl {
+    /// Backlight device in /sys/class/backlight
+    Backlight(PathBuf),
+    /// External monitor reached over DDC/CI
+    I2c(Box<DdcDisplay>),
+    /// Fake display, whose brightness is stored in this file
+    Mock(PathBuf),
+    /// Backlight device managed by KDE powerdevil, whose brightness is set over D-Bus
+    Powerdevil(PathBuf),
+    /// Backlight device managed by GNOME gsd-power, whose brightness is set over D-Bus
+    GsdPower(PathBuf),
+}
+
+/// Whether the connector belongs to a DisplayLink adapter, driven by evdi.
+/// Only recent evdi releases expose a DDC/CI bus, which is probed as usual
+pub fn is_displaylink(connector: &Path) -> bool {
+    // The connector device points to the drm card, whose device is bound to the driver
+    connector
+        .join("device/device/driver")
+        .read_link()
+        .is_ok_and(|driver| driver.file_name().is_some_and(|name| name == "evdi"))
+}
+
+/// Directories of the drm connectors (e.g. /sys/class/drm/card0-eDP-1), sorted by name
+pub fn drm_connectors() -> Result<Vec<PathBuf>> {
+    let mut connectors = fs::read_dir(SYS_DRM_ROOT)
+        .with_context(|| format!("failed to read {SYS_DRM_ROOT}"))?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| {
+            let file_name = entry.file_name();
+            let file_name = file_name.to_string_lossy();
+            file_name.starts_with("card") && file_name.contains('-')
+        })
+        .map(|entry| entry.path())
+        .collect::<Vec<_>>();
+    connectors.sort();
+    Ok(connectors)
+}
+
+/// i2c buses exposed as children of the drm connector, which is the case for DP
+pub(crate) fn connector_i2c_devices(connector: &Path) -> Vec<String> {
+    (1..=MAX_I2C_INDEX)
+        .map(|index| format!("i2c-{index}"))
+        .filter(|i2c_device| connector.join(i2c_device).exists())
+        .collect()
+}
+
+/// i2c bus the ddc symlink of the drm connector points to, which is the case for HDMI
+pub(crate) fn ddc_symlink(connector: &Path) -> Option<Result<String>> {
+    let ddc_path = connector.join("ddc").read_link().ok()?;
+    Some(
+        ddc_path
+            .file_name()
+            .map(|ddc_device| ddc_device.to_string_lossy().to_string())
+            .ok_or_else(|| eyre!("invalid ddc symlink {:?}", ddc_path)),
+    )
+}
+
+/// Connector name (e.g. eDP-1) of the drm connector directory (e.g. card0-eDP-1)
+pub fn connector_name(connector: &Path) -> String {
+    let file_name = connector.file_name().unwrap_or_default().to_string_lossy();
+    file_name
+        .split_once('-')
+        .map_or(file_name.to_string(), |(_, name)| name.to_string())
+}
+
+impl BrightnessControl {
+    /// Get the brightness control (either i2c or backlight) from the --display argument
+    /// passed by the user, which might me the name, model or description
+    pub fn get_from_name(display_arg: &str, config: &Config) -> Result<Self, eyre::Error> {
+        let br_ctl = if let Some(br_ctl) = Self::for_device(display_arg, config) {
+            br_ctl
+        } else {
+            // If we can't find the display by its name, try the model and description
+            let displays = DisplayInfo::get_displays(config)?;
+            let display = displays.iter().find(|d| d.match_name(display_arg));
+            match display {
+                Some(display) => {
+                    let br_ctl = BrightnessControl::for_device(&display.name, config);
+                    match br_ctl {
+                        Some(br_ctl) => br_ctl,
+                        None => bail!("Display {} not found", display.name),
+                    }
+                }
+                None => bail!("Display {} not found", display_arg),
+            }
+        };
+        br_ctl
+    }
+
+    /// Get the brightness control of the display with this connector name (e.g. DP-1), in
+    /// this order: mock displays, the configured backlight, the cache, the drm connector and
+    /// at last every i2c bus. `None` when nothing controls the display
+    #[instrument(level = "debug", skip(config))]
+    pub fn for_device(name: &str, config: &Config) -> Option<Result<Self>> {
+        if let Some(path) = mock::state_path(name, config) {
+            return Some(path.map(BrightnessControl::Mock));
+        }
+        let display_config = config.display_by_name(name);
+        let backend = display_config.and_then(|display_config| display_config.backend);
+        if let Some(backlight) =
+            display_config.and_then(|display_config| display_config.backlight.as_deref())
+        {
+            let path = backlight_path(backlight);
+            debug!("using pinned backlight {:?}", path);
+            return Some(if path.exists() {
+                BrightnessControl::Backlight(path).through_desktop(backend)
+            } else {
+                Err(eyre!(
+                    "backlight {backlight} configured for {name} does not exist"
+                ))
+            });
+        }
+        let connectors = match drm_connectors() {
+            Ok(connectors) => connectors,
+            Err(err) => return Some(Err(err)),
+        };
+        let br_ctl = connectors
+            .into_iter()
+            // Filter the right drm device for the display
+            .filter(|connector| connector_name(connector) == name)
+            .find_map(|connector| {
+                let mut cache = ControlCache::load();
+                if let Some(br_ctl) = cache
+                    .get(&connector)
+                    .filter(|br_ctl| backend.is_none_or(|backend| br_ctl.uses(backend)))
+                {
+                    debug!("using cached brightness control");
+                    return Some(Ok(br_ctl));
+                }
+                let br_ctl = Self::probe_connector(&connector, backend)?;
+                if let Ok(br_ctl) = &br_ctl {
+                    cache.insert(&connector, br_ctl);
+                }
+                Some(br_ctl)
+            });
+        br_ctl
+            .or_else(|| Self::find_ddc_display(name, config))
+            .map(|br_ctl| br_ctl.and_then(|br_ctl| br_ctl.through_desktop(backend)))
+    }
+
+    /// Write the backlight through powerdevil or gsd-power when they manage the brightness of
+    /// the session, as they would otherwise revert the changes. Configuring the backlight
+    /// backend opts out
+    fn through_desktop(self, backend: Option<Backend>) -> Result<Self> {
+        match (self, backend) {
+            (BrightnessControl::Backlight(path), Some(Backend::Powerdevil)) => {
+                ensure!(powerdevil::is_running(), "powerdevil is not running");
+                Ok(BrightnessControl::Powerdevil(path))
+            }
+            (BrightnessControl::Backlight(path), Some(Backend::GsdPower)) => {
+                ensure!(gsd_power::is_running(), "gsd-power is not running");
+                Ok(BrightnessControl::GsdPower(path))
+            }
+            (BrightnessControl::Backlight(path), None) if powerdevil::is_running() => {
+                debug!("using powerdevil");
+                Ok(BrightnessControl::Powerdevil(path))
+            }
+            (BrightnessControl::Backlight(path), None) if gsd_power::is_running() => {
+                debug!("using gsd-power");
+                Ok(BrightnessControl::GsdPower(path))
+            }
+            (br_ctl, _) => Ok(br_ctl),
+        }
+    }
+
+    /// Fallback for when the drm connector has no link to its i2c bus: probe all of them and
+    /// compare their EDID with the model and serial reported by the compositor
+    fn find_ddc_display(name: &str, config: &Config) -> Option<Result<Self>> {
+        let displays = DisplayInfo::get_displays(config).ok()?;
+        let display = displays.into_iter().find(|display| display.name == name)?;
+        if display.model.is_empty() && display.serial.is_empty() {
+            return None;
+        }
+        debug!("searching {name} among all the i2c devices");
+        find_ddc_display(&display.model, &display.serial)
+            .map(|ddc_display| Ok(BrightnessControl::I2c(Box::new(ddc_display))))
+    }
+
+    /// Look for the brightness control of the drm connector, backlight first and then DDC
+    /// unless the other way around has been configured
+    fn probe_connector(connector: &Path, backend: Option<Backend>) -> Option<Result<Self>> {
+        match backend {
+            Some(Backend::Ddc) => {
+                Self::probe_ddc(connector).or_else(|| Self::probe_backlight(connector))
+            }
+            Some(Backend::Backlight | Backend::Powerdevil | Backend::GsdPower) | None => {
+                Self::probe_backlight(connector).or_else(|| Self::probe_ddc(connector))
+            }
+        }
+    }
+
+    fn probe_backlight(connector: &Path) -> Option<Result<Self>> {
+        let backlight = connector_backlights(connector).into_iter().next()?;
+        debug!("using backlight {:?}", backlight);
+        Some(Ok(BrightnessControl::Backlight(backlight)))
+    }
+
+    fn probe_ddc(connector: &Path) -> Option<Result<Self>> {
+        // Try all the available i2c devices before the ddc symlink
+        // This works for DP
+        let i2c_devices = connector_i2c_devices(connector);
+        if let Some(i2c_device) = i2c_devices.first() {
+            debug!("using {i2c_device}");
+            // When there is only one bus there is no need to validate it by reading the EDID
+            let ddc_display = if i2c_devices.len() == 1 {
+                open_ddc_display(i2c_device, connector)
+            } else {
+                get_ddc_display(i2c_device)
+            };
+            return Some(ddc_display.map(|ddc_display| {
+                BrightnessControl::I2c(Box::new(DdcDisplay::new(ddc_display, i2c_device.clone())))
+            }));
+        }
+        // Fallback to the ddc symlink, works for HDMI
+        if let Some(ddc_path) = ddc_symlink(connector) {
+            let ddc_path = match ddc_path {
+                Ok(ddc_path) => ddc_path,
+                Err(err) => return Some(Err(err)),
+            };
+            debug!("using ddc symlink to {:?}", ddc_path);
+            let ddc_display = get_ddc_display(&ddc_path);
+            match ddc_display {
+                Ok(ddc_display) => Some(Ok(BrightnessControl::I2c(Box::new(DdcDisplay::new(
+                    ddc_display,
+                    ddc_path,
+                ))))),
+                Err(err) => Some(Err(err)),
+            }
+        } else if is_displaylink(connector) {
+            Some(Err(eyre!(
+                "DisplayLink adapter without DDC/CI support, its brightness cannot be controlled"
+            )))
+        } else {
+            None
+        }
+    }
+
+    /// Current and maximum brightness
+    pub fn brightness(&mut self) -> Result<(u32, u32)> {
+        self.get(Feature::Brightness)
+    }
+
+    /// Current and maximum value of the feature
+    pub fn get(&mut self, feature: Feature) -> Result<(u32, u32)> {
+        match (self, feature) {
+            (BrightnessControl::Backlight(backlight), Feature::Brightness) => {
+                backlight_brightness(Path::new(backlight))
+            }
+            (BrightnessControl::Mock(path), Feature::Brightness) => mock_brightness(path),
+            (BrightnessControl::Powerdevil(_), Feature::Brightness) => powerdevil::brightness(),
+            (BrightnessControl::GsdPower(_), Feature::Brightness) => gsd_power::brightness(),
+            (BrightnessControl::I2c(i2c_display), feature) => {
+                ddc_feature(i2c_display, feature.vcp(i2c_display))
+                    .map(|(value, max)| (value as u32, max as u32))
+                    .with_context(|| format!("failed to get the {feature}"))
+            }
+            (
+                BrightnessControl::Backlight(_)
+                | BrightnessControl::Mock(_)
+                | BrightnessControl::Powerdevil(_)
+                | BrightnessControl::GsdPower(_),
+                feature,
+            ) => {
+                bail!("the {feature} can only be controlled over DDC/CI")
+            }
+        }
+    }
+
+    /// Whether the brightness is controlled by the backend
+    pub fn uses(&self, backend: Backend) -> bool {
+        matches!(
+            (self, backend),
+            (
+                BrightnessControl::Backlight(_),
+                Backend::Backlight | Backend::Powerdevil | Backend::GsdPower
+            ) | (BrightnessControl::I2c(_), Backend::Ddc)
+                | (BrightnessControl::Powerdevil(_), Backend::Powerdevil)
+                | (BrightnessControl::GsdPower(_), Backend::GsdPower)
+        )
+    }
+
+    /// Name of the kind of brightness control
+    pub fn backend(&self) -> &'static str {
+        match self {
+            BrightnessControl::Backlight(_) => "backlight",
+            BrightnessControl::I2c(_) => "ddc",
+            BrightnessControl::Mock(_) => "mock",
+            BrightnessControl::Powerdevil(_) => "powerdevil",
+            BrightnessControl::GsdPower(_) => "gsd-power",
+        }
+    }
+
+    /// Path of the device used to control the brightness
+    pub fn device(&self) -> String {
+        match self {
+            BrightnessControl::Backlight(path)
+            | BrightnessControl::Mock(path)
+            | BrightnessControl::Powerdevil(path)
+            | BrightnessControl::GsdPower(path) => path.display().to_string(),
+            BrightnessControl::I2c(i2c_display) => format!("/dev/{}", i2c_display.i2c_device),
+        }
+    }
+
+    /// The DDC/CI display, to tune how it is controlled
+    pub fn ddc_mut(&mut self) -> Option<&mut DdcDisplay> {
+        match self {
+            BrightnessControl::I2c(i2c_display) => Some(i2c_display),
+            BrightnessControl::Backlight(_)
+            | BrightnessControl::Mock(_)
+            | BrightnessControl::Powerdevil(_)
+            | BrightnessControl::GsdPower(_) => None,
+        }
+    }
+
+    /// Manufacturer and model name read from the EDID, only available for DDC displays
+    pub fn edid_details(&self) -> Option<String> {
+        match self {
+            BrightnessControl::Backlight(_)
+            | BrightnessControl::Mock(_)
+            | BrightnessControl::Powerdevil(_)
+            | BrightnessControl::GsdPower(_) => None,
+            BrightnessControl::I2c(i2c_display) => {
+                let info = &i2c_display.display.info;
+                let details = [info.manufacturer_id.as_deref(), info.model_name.as_deref()]
+                    .into_iter()
+                    .flatten()
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                (!details.is_empty()).then_some(details)
+            }
+        }
+    }
+
+    /// Set the brightness, keeping it within `limits`. When `fade` is not zero, the brightness
+    /// gradually changes over that duration
+    pub fn set_brightness(&mut self, new_br: &str, limits: &Limits, fade: Duration) -> Result<()> {
+        self.set(Feature::Brightness, new_br, limits, fade)
+    }
+
+    /// Set the feature, parsing the value and applying `limits` and `fade` the same way as
+    /// the brightness
+    pub fn set(
+        &mut self,
+        feature: Feature,
+        new_br: &str,
+        limits: &Limits,
+        fade: Duration,
+    ) -> Result<()> {
+        let current_brightness = self.get(feature)?;
+        let mut final_brightness = calculate_new_brightness(current_brightness, new_br)
+            .with_context(|| format!("invalid {feature} value"))?;
+        if let Some(min) = limits.min {
+            let min = calculate_new_brightness(current_brightness, min)
+                .context("invalid minimum brightness")?;
+            final_brightness = final_brightness.max(min);
+        }
+        for max in &limits.max {
+            let max = calculate_new_brightness(current_brightness, max)
+                .context("invalid maximum brightness")?;
+            final_brightness = final_brightness.min(max);
+        }
+
+        if !fade.is_zero() {
+            self.fade(feature, current_brightness.0, final_brightness, fade)?;
+        }
+        self.write(feature, final_brightness)
+    }
+
+    /// Step from one value to the other over the duration, without the last step
+    fn fade(&mut self, feature: Feature, from: u32, to: u32, duration: Duration) -> Result<()> {
+        if from == to {
+            return Ok(());
+        }
+        // DDC/CI commands are slow, don't try to update as often as the backlight
+        let interval = match self {
+            BrightnessControl::I2c(_) => Duration::from_millis(100),
+            // Every step spawns busctl
+            BrightnessControl::Powerdevil(_) | BrightnessControl::GsdPower(_) => {
+                Duration::from_millis(50)
+            }
+            BrightnessControl::Backlight(_) | BrightnessControl::Mock(_) => {
+                Duration::from_millis(16)
+            }
+        };
+        let steps = (duration.as_millis() / interval.as_millis()).clamp(1, u32::MAX as u128) as u32;
+        let start = Instant::now();
+        let mut last = from;
+        for step in 1..steps {
+            let value = from as i64 + (to as i64 - from as i64) * step as i64 / steps as i64;
+            let value = value as u32;
+            if value != last {
+                self.write(feature, value)?;
+                last = value;
+            }
+            if let Some(remaining) = (interval * step).checked_sub(start.elapsed()) {
+                thread::sleep(remaining);
+            }
+        }
+        Ok(())
+    }
+
+    /// Turn the display on or off, using DPMS over DDC/CI or bl_power for backlights
+    pub fn set_power(&mut self, state: PowerState) -> Result<()> {
+        match self {
+            // powerdevil and gsd-power don't touch bl_power
+            BrightnessControl::Backlight(backlight)
+            | BrightnessControl::Powerdevil(backlight)
+            | BrightnessControl::GsdPower(backlight) => {
+                // FB_BLANK_UNBLANK, FB_BLANK_NORMAL and FB_BLANK_POWERDOWN
+                let value = match state {
+                    PowerState::On => 0,
+                    PowerState::Standby => 1,
+                    PowerState::Off => 4,
+                };
+                set_backlight_power(backlight, value)
+            }
+            BrightnessControl::I2c(i2c_display) => {
+                // The hard off (5) cannot be undone over DDC/CI, use the DPM one instead
+                let value = match state {
+                    PowerState::On => 1,
+                    PowerState::Standby => 2,
+                    PowerState::Off => 4,
+                };
+                set_ddc_feature(i2c_display, POWER_MODE_VCP, value)
+                    .context("failed to set the power mode")
+            }
+            BrightnessControl::Mock(_) => bail!("mock displays have no power control"),
+        }
+    }
+
+    /// Color temperature in Kelvin, only available for DDC/CI displays
+    pub fn temperature(&mut self) -> Result<u32> {
+        match self {
+            BrightnessControl::I2c(i2c_display) => ddc_temperature(i2c_display),
+            BrightnessControl::Backlight(_)
+            | BrightnessControl::Mock(_)
+            | BrightnessControl::Powerdevil(_)
+            | BrightnessControl::GsdPower(_) => {
+                bail!("the color temperature can only be controlled over DDC/CI")
+            }
+        }
+    }
+
+    /// Set the color temperature in Kelvin, rounded to the closest one the monitor supports
+    pub fn set_temperature(&mut self, kelvin: u32) -> Result<()> {
+        match self {
+            BrightnessControl::I2c(i2c_display) => set_ddc_temperature(i2c_display, kelvin),
+            BrightnessControl::Backlight(_)
+            | BrightnessControl::Mock(_)
+            | BrightnessControl::Powerdevil(_)
+            | BrightnessControl::GsdPower(_) => {
+                bail!("the color temperature can only be controlled over DDC/CI")
+            }
+        }
+    }
+
+    fn write(&mut self, feature: Feature, value: u32) -> Result<()> {
+        match (self, feature) {
+            (BrightnessControl::Backlight(backlight), Feature::Brightness) => {
+                set_backlight_brightness(Path::new(backlight), value)
+            }
+            (BrightnessControl::Mock(path), Feature::Brightness) => {
+                set_mock_brightness(path, value)
+            }
+            (BrightnessControl::Powerdevil(_), Feature::Brightness) => {
+                powerdevil::set_brightness(value)
+            }
+            (BrightnessControl::GsdPower(_), Feature::Brightness) => {
+                gsd_power::set_brightness(value)
+            }
+            (BrightnessControl::I2c(i2c_display), feature) => {
+                let vcp = feature.vcp(i2c_display);
+                set_ddc_feature(i2c_display, vcp, value.try_into()?)
+                    .with_context(|| format!("failed to set the {feature}"))
+            }
+            (
+                BrightnessControl::Backlight(_)
+                | BrightnessControl::Mock(_)
+                | BrightnessControl::Powerdevil(_)
+                | BrightnessControl::GsdPower(_),
+                feature,
+            ) => {
+                bail!("the {feature} can only be controlled over DDC/CI")
+            }
+        }
+    }
+}
+
+/// Calculate the new brightness value based on the current brightness value
+pub fn calculate_new_brightness(
+    current_brightness: (u32, u32),
+    new_brightness: &str,
+) -> Result<u32> {
+    // If the brightness string start with a '-' it means relative decrease
+    // If the brightness string start with a '+' it means relative increase
+    // If the brightness string is a number it means absolute value
+    // If the brightness ends with a '%' it means percentage
+    // Apply brightness reletive increase/decrease with percentage as well
+
+    let brightness = new_brightness.trim();
+    ensure!(!brightness.is_empty(), "brightness cannot be empty");
+    let first_char = brightness.chars().next().unwrap();
+    let (br, max_br) = current_brightness;
+    let mut new_br = if first_char == '+' || first_char == '-' {
+        &brightness[1..]
+    } else {
+        brightness
+    };
+    ensure!(!new_br.is_empty(), "invalid brightness value");
+    let percentage = if new_br.ends_with('%') {
+        new_br = &new_br[..new_br.len() - 1];
+        true
+    } else {
+        false
+    };
+    // if the value provided is a percentage, calculate the absolute value with
+    // new_br * max_br / 100. Percentages can have decimals, which matter for backlights
+    // with thousands of steps
+    let set_val = if percentage {
+        let new_br = new_br.parse::<f64>().context("invalid brightness value")?;
+        ensure!(
+            new_br.is_finite() && new_br >= 0.0,
+            "invalid brightness value"
+        );
+        // f64 is precise enough for any u32 max_brightness
+        (new_br * max_br as f64 / 100.0).min(u32::MAX as f64) as u32
+    } else {
+        new_br.parse::<u32>().context("invalid brightness value")?
+    };
+    let new_br = match first_char {
+        '+' => {
+            // We do not want to overflow the brightness value
+            br.saturating_add(set_val)
+        }
+        '-' => br.saturating_sub(set_val),
+        _ => set_val,
+    };
+
+    // Apply max allowed values
+    Ok(new_br.min(max_br))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn absolute_brightness() {
+        assert_eq!(calculate_new_brightness((50, 100), "30").unwrap(), 30);
+        assert_eq!(calculate_new_brightness((50, 100), " 30 ").unwrap(), 30);
+        // Values above the maximum are capped
+        assert_eq!(calculate_new_brightness((50, 100), "300").unwrap(), 100);
+    }
+
+    #[test]
+    fn relative_brightness() {
+        assert_eq!(calculate_new_brightness((50, 100), "+10").unwrap(), 60);
+        assert_eq!(calculate_new_brightness((50, 100), "-10").unwrap(), 40);
+        assert_eq!(calculate_new_brightness((50, 100), "-80").unwrap(), 0);
+        assert_eq!(calculate_new_brightness((50, 100), "+80").unwrap(), 100);
+        assert_eq!(
+            calculate_new_brightness((u32::MAX - 1, u32::MAX), "+10").unwrap(),
+            u32::MAX
+        );
+    }
+
+    #[test]
+    fn percentage_brightness() {
+        assert_eq!(calculate_new_brightness((0, 255), "50%").unwrap(), 127);
+        assert_eq!(calculate_new_brightness((100, 1000), "+10%").unwrap(), 200);
+        assert_eq!(calculate_new_brightness((100, 1000), "-20%").unwrap(), 0);
+    }
+
+    #[test]
+    fn fractional_percentage_brightness() {
+        assert_eq!(calculate_new_brightness((0, 19200), "0.5%").unwrap(), 96);
+        assert_eq!(
+            calculate_new_brightness((96, 19200), "+0.25%").unwrap(),
+            144
+        );
+        assert_eq!(calculate_new_brightness((0, 19200), "12.5%").unwrap(), 2400);
+        // Below one step of coarse displays
+        assert_eq!(calculate_new_brightness((0, 100), "0.5%").unwrap(), 0);
+    }
+
+    #[test]
+    fn invalid_brightness() {
+        for value in [
+            "", "+", "-", "%", "abc", "-1.5", "nan%", "inf%", "-nan%", "10%%",
+        ] {
+            assert!(
+                calculate_new_brightness((50, 100), value).is_err(),
+                "{value} should be rejected"
+            );
+        }
+    }
+}
@@ -0,0 +1,48 @@
+use std::process::Command;
+
+use eyre::{ensure, Context, ContextCompat, Result};
+
+/// Run busctl on the session bus, returning the reply in the busctl format, e.g. `i 500`.
+/// `daemon` names the service in the errors
+pub fn session(daemon: &str, args: &[&str]) -> Result<String> {
+    let output = Command::new("busctl")
+        .arg("--user")
+        .args(args)
+        .output()
+        .context("failed to run busctl")?;
+    ensure!(
+        output.status.success(),
+        "request to {daemon} failed: {}",
+        String::from_utf8_lossy(&output.stderr).trim()
+    );
+    Ok(String::from_utf8(output.stdout)?)
+}
+
+/// Value of a busctl reply made of a single int32
+pub fn parse_int(daemon: &str, reply: &str) -> Result<i32> {
+    reply
+        .trim()
+        .strip_prefix("i ")
+        .and_then(|value| value.parse().ok())
+        .with_context(|| format!("unexpected reply from {daemon}: {reply}"))
+}
+
+/// Whether the desktop of the session, from $XDG_CURRENT_DESKTOP, is this one
+pub fn is_desktop(name: &str) -> bool {
+    std::env::var("XDG_CURRENT_DESKTOP")
+        .is_ok_and(|desktop| desktop.split(':').any(|desktop| desktop == name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn int_replies() {
+        assert_eq!(parse_int("test", "i 500\n").unwrap(), 500);
+        assert_eq!(parse_int("test", "i -1").unwrap(), -1);
+        for reply in ["", "i", "u 5", "i five", "s \"500\""] {
+            assert!(parse_int("test", reply).is_err(), "{reply} should be rejected");
+        }
+    }
+}
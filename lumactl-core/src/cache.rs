@@ -0,0 +1,157 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    process,
+    sync::Mutex,
+};
+
+use tracing::debug;
+
+use crate::{
+    brightness_control::BrightnessControl,
+    ddc::{open_ddc_display, DdcDisplay},
+};
+
+const CACHE_FILE: &str = "controls.json";
+
+/// Serializes the updates of the threads in `run_parallel`, which would otherwise drop
+/// each other's entries
+static UPDATE_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(serde::Serialize, serde::Deserialize)]
+enum CachedControl {
+    Backlight(PathBuf),
+    I2c(String),
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct CacheEntry {
+    edid_hash: u64,
+    control: CachedControl,
+}
+
+/// Brightness control picked for each drm connector, so that the following invocations
+/// don't need to walk sysfs and probe every i2c bus again
+#[derive(Default, serde::Serialize, serde::Deserialize)]
+pub struct ControlCache {
+    entries: HashMap<String, CacheEntry>,
+}
+
+impl ControlCache {
+    pub fn load() -> Self {
+        cache_path()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    /// Write the cache to a temporary file and rename it, so that concurrent invocations
+    /// never read a truncated file
+    fn save(&self) {
+        let res = xdg::BaseDirectories::with_prefix("lumactl")
+            .map_err(eyre::Error::from)
+            .and_then(|xdg_dirs| Ok(xdg_dirs.place_cache_file(CACHE_FILE)?))
+            .and_then(|path| {
+                let tmp_path = path.with_extension(format!("json.{}", process::id()));
+                fs::write(&tmp_path, serde_json::to_string(self)?)?;
+                Ok(fs::rename(tmp_path, path)?)
+            });
+        if let Err(err) = res {
+            debug!("unable to save the brightness control cache: {err}");
+        }
+    }
+
+    /// Get the brightness control of the connector, as long as the same monitor is connected
+    pub fn get(&self, connector: &Path) -> Option<BrightnessControl> {
+        let entry = self.entries.get(&connector_key(connector))?;
+        if entry.edid_hash != edid_hash(connector) {
+            debug!("cached brightness control for {:?} is stale", connector);
+            return None;
+        }
+        match &entry.control {
+            CachedControl::Backlight(path) if path.exists() => {
+                Some(BrightnessControl::Backlight(path.clone()))
+            }
+            // i2c adapter numbers change across boots and hotplugs, the bus must still
+            // belong to the connector
+            CachedControl::I2c(i2c_device)
+                if Path::new("/dev").join(i2c_device).exists()
+                    && is_connector_bus(connector, i2c_device) =>
+            {
+                // The EDID hash has been checked already, skip reading it again over DDC
+                open_ddc_display(i2c_device, connector)
+                    .ok()
+                    .map(|ddc_display| {
+                        BrightnessControl::I2c(Box::new(DdcDisplay::new(
+                            ddc_display,
+                            i2c_device.clone(),
+                        )))
+                    })
+            }
+            _ => {
+                debug!("cached brightness control for {:?} is gone", connector);
+                None
+            }
+        }
+    }
+
+    /// Remember the brightness control of the connector, merging it with the entries saved
+    /// in the meantime
+    pub fn insert(&mut self, connector: &Path, br_ctl: &BrightnessControl) {
+        let control = match br_ctl {
+            BrightnessControl::Backlight(path) => CachedControl::Backlight(path.clone()),
+            BrightnessControl::I2c(i2c_display) => {
+                CachedControl::I2c(i2c_display.i2c_device.clone())
+            }
+            BrightnessControl::Mock(_)
+            | BrightnessControl::Powerdevil(_)
+            | BrightnessControl::GsdPower(_) => return,
+        };
+        let _guard = UPDATE_LOCK.lock().unwrap_or_else(|err| err.into_inner());
+        *self = Self::load();
+        self.entries.insert(
+            connector_key(connector),
+            CacheEntry {
+                edid_hash: edid_hash(connector),
+                control,
+            },
+        );
+        self.save();
+    }
+}
+
+/// Whether the i2c bus is exposed by the connector, either as a child (DP) or through
+/// the ddc symlink (HDMI)
+fn is_connector_bus(connector: &Path, i2c_device: &str) -> bool {
+    connector.join(i2c_device).exists()
+        || connector
+            .join("ddc")
+            .read_link()
+            .is_ok_and(|ddc| ddc.file_name().is_some_and(|name| name == i2c_device))
+}
+
+fn cache_path() -> Option<PathBuf> {
+    xdg::BaseDirectories::with_prefix("lumactl")
+        .ok()
+        .map(|xdg_dirs| xdg_dirs.get_cache_file(CACHE_FILE))
+}
+
+fn connector_key(connector: &Path) -> String {
+    connector
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string()
+}
+
+/// FNV-1a hash of the EDID exposed by the drm connector, which is cheap to read and stays
+/// the same across builds
+fn edid_hash(connector: &Path) -> u64 {
+    fs::read(connector.join("edid"))
+        .unwrap_or_default()
+        .iter()
+        .fold(0xcbf29ce484222325, |hash, byte| {
+            (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+        })
+}
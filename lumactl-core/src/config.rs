@@ -0,0 +1,375 @@
+use std::collections::HashMap;
+
+use chrono::{Local, NaiveTime};
+use eyre::{ensure, Context, Result};
+
+use crate::{
+    ddc::{DdcTuning, MAX_SLEEP_MULTIPLIER},
+    display_info::{is_connector_name, DisplayInfo},
+};
+
+const CONFIG_FILE: &str = "config.toml";
+
+#[derive(serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+/// Options read from the configuration file, with their defaults
+pub struct Config {
+    /// Maximum brightness allowed during the night, either absolute or a percentage
+    pub night_max: Option<String>,
+    /// Start of the night, in the HH:MM format
+    pub night_start: String,
+    /// End of the night, in the HH:MM format
+    pub night_end: String,
+    /// Number of fake displays to expose, for testing without controllable hardware
+    pub mock_displays: u32,
+    /// Amount used by up, down and by a bare + or - in set
+    pub step: String,
+    /// Brightness set by toggle when the display is brighter than it
+    pub toggle_low: String,
+    /// Brightness set by toggle otherwise
+    pub toggle_high: String,
+    /// Display used when none is passed, instead of all of them
+    pub default_display: Option<String>,
+    /// Named groups of displays, usable wherever a display name is expected
+    pub groups: HashMap<String, Vec<String>>,
+    /// VCP feature code used for the brightness of DDC displays, 0x10 if not set
+    pub brightness_vcp: Option<u8>,
+    /// How many times a failed DDC/CI command is repeated, 2 by default
+    pub ddc_retries: u32,
+    /// Factor applied to the delays between DDC/CI commands, increase it for flaky monitors.
+    /// At most 10
+    pub ddc_sleep_multiplier: f32,
+    /// Per-display options, keyed by the display name, model or description
+    pub display: HashMap<String, DisplayConfig>,
+}
+
+/// Kind of brightness control, for displays that have more than one
+#[derive(Clone, Copy, Debug, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Backend {
+    /// Backlight device, for internal panels
+    Backlight,
+    /// DDC/CI, for external monitors
+    Ddc,
+    /// The backlight through KDE powerdevil, used by default on KDE sessions
+    Powerdevil,
+    /// The backlight through GNOME gsd-power, used by default on GNOME sessions
+    #[serde(rename = "gsd-power")]
+    GsdPower,
+}
+
+/// Options of a single display, in the `[display.<name>]` sections
+#[derive(Default, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DisplayConfig {
+    /// Another name for the display, usable wherever a display name is expected
+    pub alias: Option<String>,
+    /// Never touch this display unless it's explicitly targeted
+    pub ignore: bool,
+    /// Brightness control to use when both a backlight and DDC/CI are available
+    pub backend: Option<Backend>,
+    /// Name of the backlight device in /sys/class/backlight to use, for machines with more
+    /// than one (e.g. intel_backlight and nvidia_0 on hybrid laptops)
+    pub backlight: Option<String>,
+    /// Lowest brightness that can be set, either absolute or a percentage
+    pub min: Option<String>,
+    /// Highest brightness that can be set, either absolute or a percentage
+    pub max: Option<String>,
+    /// Amount used by up, down and by a bare + or - in set, instead of the global one
+    pub step: Option<String>,
+    /// Low level used by toggle, instead of the global one
+    pub toggle_low: Option<String>,
+    /// High level used by toggle, instead of the global one
+    pub toggle_high: Option<String>,
+    /// VCP feature code used for the brightness of DDC displays, instead of the global one
+    pub brightness_vcp: Option<u8>,
+    /// How many times a failed DDC/CI command is repeated, instead of the global value
+    pub ddc_retries: Option<u32>,
+    /// Factor applied to the delays between DDC/CI commands, instead of the global value
+    pub ddc_sleep_multiplier: Option<f32>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            night_max: None,
+            night_start: "22:00".to_string(),
+            night_end: "07:00".to_string(),
+            mock_displays: 0,
+            step: "5%".to_string(),
+            toggle_low: "20%".to_string(),
+            toggle_high: "100%".to_string(),
+            default_display: None,
+            groups: HashMap::new(),
+            brightness_vcp: None,
+            ddc_retries: DdcTuning::default().retries,
+            ddc_sleep_multiplier: DdcTuning::default().sleep_multiplier,
+            display: HashMap::new(),
+        }
+    }
+}
+
+impl Config {
+    /// Load the configuration from $XDG_CONFIG_HOME/lumactl/config.toml, if it exists
+    pub fn load() -> Result<Self> {
+        let xdg_dirs = xdg::BaseDirectories::with_prefix("lumactl")?;
+        match xdg_dirs.find_config_file(CONFIG_FILE) {
+            Some(path) => {
+                let content = std::fs::read_to_string(&path)
+                    .with_context(|| format!("failed to read {:?}", path))?;
+                let config: Self = toml::from_str(&content)
+                    .with_context(|| format!("failed to parse {:?}", path))?;
+                config
+                    .validate()
+                    .with_context(|| format!("invalid configuration in {:?}", path))?;
+                Ok(config)
+            }
+            None => Ok(Self::default()),
+        }
+    }
+
+    /// Reject the values that can be parsed but not used
+    fn validate(&self) -> Result<()> {
+        let multipliers = std::iter::once((
+            "ddc_sleep_multiplier".to_string(),
+            Some(self.ddc_sleep_multiplier),
+        ))
+        .chain(self.display.iter().map(|(name, display)| {
+            (
+                format!("display.{name}.ddc_sleep_multiplier"),
+                display.ddc_sleep_multiplier,
+            )
+        }));
+        for (option, multiplier) in multipliers {
+            if let Some(multiplier) = multiplier {
+                ensure!(
+                    (0.0..=MAX_SLEEP_MULTIPLIER).contains(&multiplier),
+                    "{option} must be between 0 and {MAX_SLEEP_MULTIPLIER}, got {multiplier}"
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Options of the display passed by the user, which might be its name, model or description
+    pub fn display_by_name(&self, name: &str) -> Option<&DisplayConfig> {
+        if self.display.is_empty() {
+            return None;
+        }
+        self.display.get(name).or_else(|| {
+            let displays = DisplayInfo::get_displays(self).ok()?;
+            displays
+                .iter()
+                .find(|display| display.name == name)
+                .or_else(|| displays.iter().find(|display| display.match_name(name)))
+                .and_then(|display| self.display(display))
+        })
+    }
+
+    /// Options of the display, matching the connector name first and then the model or
+    /// description. The names must match exactly, so that DP-1 doesn't apply to eDP-1 or DP-10
+    pub fn display(&self, display: &DisplayInfo) -> Option<&DisplayConfig> {
+        self.display.get(&display.name).or_else(|| {
+            [&display.model, &display.description]
+                .into_iter()
+                .filter(|name| !name.is_empty() && !is_connector_name(name))
+                .find_map(|name| self.display.get(name))
+        })
+    }
+
+    /// Replace a bare + or - with the step configured for the display
+    pub fn apply_step(&self, brightness: &str, display: Option<&DisplayConfig>) -> String {
+        match brightness.trim() {
+            sign @ ("+" | "-") => {
+                let step = display
+                    .and_then(|display| display.step.as_deref())
+                    .unwrap_or(&self.step);
+                format!("{sign}{step}")
+            }
+            brightness => brightness.to_string(),
+        }
+    }
+
+    /// Low and high levels used by toggle for the display
+    pub fn toggle_levels<'a>(&'a self, display: Option<&'a DisplayConfig>) -> (&'a str, &'a str) {
+        (
+            display
+                .and_then(|display| display.toggle_low.as_deref())
+                .unwrap_or(&self.toggle_low),
+            display
+                .and_then(|display| display.toggle_high.as_deref())
+                .unwrap_or(&self.toggle_high),
+        )
+    }
+
+    /// VCP feature code used for the brightness of the display, if not the standard one
+    pub fn brightness_vcp(&self, display: Option<&DisplayConfig>) -> Option<u8> {
+        display
+            .and_then(|display| display.brightness_vcp)
+            .or(self.brightness_vcp)
+    }
+
+    /// Use this VCP feature code for the brightness of every display, ignoring the one
+    /// configured per display
+    pub fn override_brightness_vcp(&mut self, vcp: u8) {
+        self.brightness_vcp = Some(vcp);
+        for display in self.display.values_mut() {
+            display.brightness_vcp = None;
+        }
+    }
+
+    /// DDC/CI tuning of the display, falling back to the global options
+    pub fn ddc_tuning(&self, display: Option<&DisplayConfig>) -> DdcTuning {
+        DdcTuning {
+            retries: display
+                .and_then(|display| display.ddc_retries)
+                .unwrap_or(self.ddc_retries),
+            sleep_multiplier: display
+                .and_then(|display| display.ddc_sleep_multiplier)
+                .unwrap_or(self.ddc_sleep_multiplier),
+        }
+    }
+
+    /// The brightness cap to apply right now, if any
+    pub fn brightness_cap(&self) -> Result<Option<&str>> {
+        self.brightness_cap_at(Local::now().time())
+    }
+
+    fn brightness_cap_at(&self, now: NaiveTime) -> Result<Option<&str>> {
+        let Some(night_max) = &self.night_max else {
+            return Ok(None);
+        };
+        let start = parse_time(&self.night_start)?;
+        let end = parse_time(&self.night_end)?;
+        // The night usually spans across midnight
+        let is_night = if start <= end {
+            start <= now && now < end
+        } else {
+            now >= start || now < end
+        };
+        Ok(is_night.then_some(night_max.as_str()))
+    }
+}
+
+fn parse_time(time: &str) -> Result<NaiveTime> {
+    NaiveTime::parse_from_str(time, "%H:%M")
+        .with_context(|| format!("invalid time {time}, expected HH:MM"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn night(start: &str, end: &str) -> Config {
+        Config {
+            night_max: Some("40%".to_string()),
+            night_start: start.to_string(),
+            night_end: end.to_string(),
+            ..Config::default()
+        }
+    }
+
+    fn cap_at(config: &Config, time: &str) -> Option<String> {
+        config
+            .brightness_cap_at(parse_time(time).unwrap())
+            .unwrap()
+            .map(str::to_string)
+    }
+
+    fn display_info(name: &str, model: &str) -> DisplayInfo {
+        DisplayInfo {
+            make: String::new(),
+            model: model.to_string(),
+            name: name.to_string(),
+            description: format!("{model} ({name})"),
+            serial: String::new(),
+            focused: false,
+            primary: false,
+        }
+    }
+
+    #[test]
+    fn display_sections_match_exactly() {
+        let config: Config = toml::from_str(
+            r#"
+            [display.DP-1]
+            ignore = true
+            [display."DELL U2720Q"]
+            step = "10%"
+            "#,
+        )
+        .unwrap();
+        assert!(config.display(&display_info("DP-1", "")).unwrap().ignore);
+        assert!(config.display(&display_info("eDP-1", "")).is_none());
+        assert!(config.display(&display_info("DP-10", "")).is_none());
+        let dell = config
+            .display(&display_info("DP-2", "DELL U2720Q"))
+            .unwrap();
+        assert_eq!(dell.step.as_deref(), Some("10%"));
+        assert!(config
+            .display(&display_info("DP-3", "DELL U2720QM"))
+            .is_none());
+        // Keys naming a connector never match a model
+        assert!(config.display(&display_info("HDMI-A-1", "DP-1")).is_none());
+    }
+
+    #[test]
+    fn invalid_sleep_multipliers() {
+        for multiplier in ["-1.0", "nan", "inf", "1e30", "10.5"] {
+            let config: Config =
+                toml::from_str(&format!("ddc_sleep_multiplier = {multiplier}")).unwrap();
+            assert!(
+                config.validate().is_err(),
+                "{multiplier} should be rejected"
+            );
+            let config: Config = toml::from_str(&format!(
+                "[display.DP-1]\nddc_sleep_multiplier = {multiplier}"
+            ))
+            .unwrap();
+            assert!(
+                config.validate().is_err(),
+                "{multiplier} should be rejected"
+            );
+        }
+        for multiplier in ["0.0", "1.5", "10.0"] {
+            let config: Config =
+                toml::from_str(&format!("ddc_sleep_multiplier = {multiplier}")).unwrap();
+            assert!(config.validate().is_ok(), "{multiplier} should be accepted");
+        }
+    }
+
+    #[test]
+    fn brightness_cap_across_midnight() {
+        let config = night("22:00", "07:00");
+        assert_eq!(cap_at(&config, "21:59"), None);
+        assert_eq!(cap_at(&config, "22:00").as_deref(), Some("40%"));
+        assert_eq!(cap_at(&config, "00:00").as_deref(), Some("40%"));
+        assert_eq!(cap_at(&config, "06:59").as_deref(), Some("40%"));
+        assert_eq!(cap_at(&config, "07:00"), None);
+        assert_eq!(cap_at(&config, "12:00"), None);
+    }
+
+    #[test]
+    fn brightness_cap_within_a_day() {
+        let config = night("01:00", "05:30");
+        assert_eq!(cap_at(&config, "00:59"), None);
+        assert_eq!(cap_at(&config, "01:00").as_deref(), Some("40%"));
+        assert_eq!(cap_at(&config, "05:29").as_deref(), Some("40%"));
+        assert_eq!(cap_at(&config, "05:30"), None);
+        assert_eq!(cap_at(&config, "23:00"), None);
+    }
+
+    #[test]
+    fn brightness_cap_without_night_max() {
+        let config = Config::default();
+        assert_eq!(cap_at(&config, "23:00"), None);
+    }
+
+    #[test]
+    fn brightness_cap_invalid_time() {
+        let config = night("10pm", "07:00");
+        assert!(config
+            .brightness_cap_at(parse_time("23:00").unwrap())
+            .is_err());
+    }
+}
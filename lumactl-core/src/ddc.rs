@@ -0,0 +1,254 @@
+use std::fs::{self, File};
+use std::os::unix::fs::MetadataExt;
+use std::path::Path;
+use std::time::Duration;
+
+use ddc::commands::{Command, CommandResult, GetVcpFeature, SetVcpFeature};
+use ddc::{DdcCommandRaw, DdcCommandRawMarker, Delay, Edid, DELAY_COMMAND_FAILED_MS};
+use ddc_hi::Backend;
+use ddc_hi::DisplayInfo;
+use ddc_hi::Handle;
+use ddc_i2c::I2cDdc;
+use eyre::ensure;
+use eyre::eyre;
+use eyre::Context;
+use eyre::Result;
+use i2c_linux::I2c;
+use tracing::{debug, instrument};
+
+/// VCP feature code of the brightness, as defined by MCCS
+pub const BRIGHTNESS_VCP: u8 = 0x10;
+/// VCP feature code of the contrast, as defined by MCCS
+pub const CONTRAST_VCP: u8 = 0x12;
+/// VCP feature code of the power mode (DPMS), as defined by MCCS
+pub const POWER_MODE_VCP: u8 = 0xd6;
+/// VCP feature code of the step, in Kelvin, used by the color temperature request
+const COLOR_TEMPERATURE_INCREMENT_VCP: u8 = 0x0b;
+/// VCP feature code of the color temperature request, in steps above 3000K
+const COLOR_TEMPERATURE_REQUEST_VCP: u8 = 0x0c;
+/// Color temperature of the request value 0
+const COLOR_TEMPERATURE_BASE: u32 = 3000;
+
+/// Retries wait at most 40ms * 2^6, about 2.5s, before the sleep multiplier
+const MAX_BACKOFF_SHIFT: u32 = 6;
+/// Failed commands are repeated twice by default, since DDC/CI over i2c is often unreliable
+const DEFAULT_RETRIES: u32 = 2;
+/// Highest sleep multiplier, which already makes a slow DDC/CI read take seconds
+pub(crate) const MAX_SLEEP_MULTIPLIER: f32 = 10.0;
+
+/// Tuning of the DDC/CI communication, like ddcutil's --maxtries and --sleep-multiplier
+#[derive(Clone, Copy, Debug)]
+pub struct DdcTuning {
+    /// How many times a failed command is repeated
+    pub retries: u32,
+    /// Factor applied to the delays required by the DDC/CI specification
+    pub sleep_multiplier: f32,
+}
+
+impl Default for DdcTuning {
+    fn default() -> Self {
+        Self {
+            retries: DEFAULT_RETRIES,
+            sleep_multiplier: 1.0,
+        }
+    }
+}
+
+/// A display controlled over DDC/CI
+pub struct DdcDisplay {
+    /// Handle of the i2c bus along with the information read from the EDID
+    pub display: ddc_hi::Display,
+    /// Some monitors expose the brightness on a vendor specific code instead of 0x10
+    pub brightness_vcp: u8,
+    /// Name of the i2c device in /dev, e.g. i2c-4
+    pub i2c_device: String,
+    /// Retries and delays of the DDC/CI commands
+    pub tuning: DdcTuning,
+}
+
+impl DdcDisplay {
+    /// Control the display over the standard brightness code, with the default tuning
+    pub fn new(display: ddc_hi::Display, i2c_device: String) -> Self {
+        Self {
+            display,
+            brightness_vcp: BRIGHTNESS_VCP,
+            i2c_device,
+            tuning: DdcTuning::default(),
+        }
+    }
+}
+
+type I2cDevice = I2cDdc<I2c<File>>;
+
+/// Open the i2c device, returning it along with its device number used as id
+fn open_i2c(name: &str) -> Result<(I2cDevice, u64)> {
+    let i2c_dev = Path::new("/dev").join(name);
+    let ddc = I2cDdc::new(I2c::from_path(i2c_dev)?);
+    let id = ddc
+        .inner_ref()
+        .inner_ref()
+        .metadata()
+        .map(|meta| meta.rdev())
+        .unwrap_or_default();
+    Ok((ddc, id))
+}
+
+/// Name of the i2c device from the id given by ddc_hi, which is its device number
+pub fn i2c_device_name(id: &str) -> Option<String> {
+    let rdev: u64 = id.parse().ok()?;
+    // Same as glibc minor(), i2c-dev uses the adapter number as minor
+    let minor = ((rdev >> 12) & 0xffff_ff00) | (rdev & 0xff);
+    Some(format!("i2c-{minor}"))
+}
+
+/// Find the display by probing every i2c device, matching the model and serial from the EDID.
+/// Empty model or serial are not compared
+#[instrument(level = "debug")]
+pub fn find_ddc_display(model: &str, serial: &str) -> Option<DdcDisplay> {
+    ddc_hi::Display::enumerate()
+        .into_iter()
+        .find(|display| {
+            let info = &display.info;
+            (model.is_empty() || info.model_name.as_deref() == Some(model))
+                && (serial.is_empty()
+                    || info.serial_number.as_deref() == Some(serial)
+                    || info
+                        .serial
+                        .is_some_and(|number| number.to_string() == serial))
+        })
+        .and_then(|display| {
+            let i2c_device = i2c_device_name(&display.info.id)?;
+            Some(DdcDisplay::new(display, i2c_device))
+        })
+}
+
+#[instrument(level = "debug")]
+pub(crate) fn get_ddc_display(name: &str) -> Result<ddc_hi::Display> {
+    let (mut ddc, id) = open_i2c(name)?;
+    let mut edid = vec![0u8; 0x100];
+    ddc.read_edid(0, &mut edid)
+        .map_err(|e| eyre!("failed to read EDID for i2c-{}: {}", id, e))?;
+    let display_info = DisplayInfo::from_edid(Backend::I2cDevice, id.to_string(), edid)
+        .map_err(|e| eyre!("failed to parse EDID for i2c-{}: {}", id, e))?;
+    Ok(ddc_hi::Display::new(Handle::I2cDevice(ddc), display_info))
+}
+
+/// Open the i2c device without reading the EDID over DDC, which is slow. The EDID exposed by
+/// the drm connector is used instead, when available
+#[instrument(level = "debug")]
+pub(crate) fn open_ddc_display(name: &str, connector: &Path) -> Result<ddc_hi::Display> {
+    let (ddc, id) = open_i2c(name)?;
+    let display_info = fs::read(connector.join("edid"))
+        .ok()
+        .filter(|edid| !edid.is_empty())
+        .and_then(|edid| DisplayInfo::from_edid(Backend::I2cDevice, id.to_string(), edid).ok())
+        .unwrap_or_else(|| DisplayInfo::new(Backend::I2cDevice, id.to_string()));
+    Ok(ddc_hi::Display::new(Handle::I2cDevice(ddc), display_info))
+}
+
+/// Current and maximum value of the VCP feature
+#[instrument(
+    level = "debug",
+    skip(ddc),
+    fields(id = %ddc.display.info.id, model = ?ddc.display.info.model_name)
+)]
+pub fn ddc_feature(ddc: &mut DdcDisplay, vcp: u8) -> Result<(u16, u16)> {
+    execute(ddc, GetVcpFeature::new(vcp)).map(|val| (val.value(), val.maximum()))
+}
+
+#[instrument(
+    level = "debug",
+    skip(ddc),
+    fields(id = %ddc.display.info.id, model = ?ddc.display.info.model_name)
+)]
+/// Set the value of the VCP feature
+pub fn set_ddc_feature(ddc: &mut DdcDisplay, vcp: u8, value: u16) -> Result<()> {
+    execute(ddc, SetVcpFeature::new(vcp, value))
+}
+
+/// The color temperature in Kelvin along with the step the monitor supports
+fn color_temperature_increment(ddc: &mut DdcDisplay) -> Result<u32> {
+    let (increment, _) = ddc_feature(ddc, COLOR_TEMPERATURE_INCREMENT_VCP)
+        .context("the monitor does not support setting the color temperature")?;
+    ensure!(
+        increment > 0,
+        "the monitor reports an invalid color temperature step"
+    );
+    Ok(increment as u32)
+}
+
+/// Color temperature in Kelvin
+pub fn ddc_temperature(ddc: &mut DdcDisplay) -> Result<u32> {
+    let increment = color_temperature_increment(ddc)?;
+    let (request, _) = ddc_feature(ddc, COLOR_TEMPERATURE_REQUEST_VCP)
+        .context("failed to get the color temperature")?;
+    Ok(COLOR_TEMPERATURE_BASE + request as u32 * increment)
+}
+
+/// Set the color temperature, rounded to the closest step supported by the monitor
+pub fn set_ddc_temperature(ddc: &mut DdcDisplay, kelvin: u32) -> Result<()> {
+    let increment = color_temperature_increment(ddc)?;
+    let (_, max_request) = ddc_feature(ddc, COLOR_TEMPERATURE_REQUEST_VCP)
+        .context("failed to get the color temperature")?;
+    let request = (kelvin.saturating_sub(COLOR_TEMPERATURE_BASE) + increment / 2) / increment;
+    let request = request.min(max_request as u32) as u16;
+    set_ddc_feature(ddc, COLOR_TEMPERATURE_REQUEST_VCP, request)
+        .context("failed to set the color temperature")
+}
+
+/// Execute the command, retrying it as many times as configured. The wait before each retry
+/// doubles, giving the monitor more time to recover from transient failures
+fn execute<C: Command>(ddc: &mut DdcDisplay, command: C) -> Result<C::Ok> {
+    let DdcTuning {
+        retries,
+        sleep_multiplier,
+    } = ddc.tuning;
+    let Handle::I2cDevice(i2c) = &mut ddc.display.handle;
+    let mut attempt = 0;
+    loop {
+        match execute_once(i2c, &command, sleep_multiplier) {
+            Ok(res) => return Ok(res),
+            Err(err) if attempt < retries => {
+                attempt += 1;
+                debug!("DDC/CI command failed ({err}), retrying {attempt}/{retries}");
+                let backoff = DELAY_COMMAND_FAILED_MS << attempt.min(MAX_BACKOFF_SHIFT);
+                i2c.set_sleep_delay(Delay::new(scaled_delay(backoff, sleep_multiplier)));
+            }
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+/// Same as `ddc::DdcCommand::execute`, but with the delays scaled by the sleep multiplier
+fn execute_once<C: Command>(
+    i2c: &mut I2cDevice,
+    command: &C,
+    sleep_multiplier: f32,
+) -> Result<C::Ok> {
+    let scaled = |ms| scaled_delay(ms, sleep_multiplier);
+    let mut data = [0u8; 36];
+    command.encode(&mut data).map_err(eyre::Error::msg)?;
+    let mut out = [0u8; 36 + 3];
+    let out = if C::Ok::MAX_LEN > 0 {
+        &mut out[..C::Ok::MAX_LEN + 3]
+    } else {
+        &mut []
+    };
+    let res = i2c
+        .execute_raw(&data[..command.len()], out, scaled(C::DELAY_RESPONSE_MS))
+        .map_err(eyre::Error::msg)
+        .and_then(|res| C::Ok::decode(res).map_err(eyre::Error::msg));
+    let delay = if res.is_ok() {
+        C::DELAY_COMMAND_MS
+    } else {
+        DELAY_COMMAND_FAILED_MS
+    };
+    i2c.set_sleep_delay(Delay::new(scaled(delay)));
+    res
+}
+
+fn scaled_delay(ms: u64, sleep_multiplier: f32) -> Duration {
+    // The config is validated, but DdcTuning can also be built by library users
+    let sleep_multiplier = sleep_multiplier.clamp(0.0, MAX_SLEEP_MULTIPLIER);
+    Duration::try_from_secs_f32(ms as f32 * sleep_multiplier / 1000.0).unwrap_or(Duration::ZERO)
+}
@@ -0,0 +1,82 @@
+use std::{fs, path::PathBuf};
+
+use eyre::Result;
+
+use crate::{
+    backlight::connector_backlights,
+    brightness_control::{
+        connector_i2c_devices, connector_name, ddc_symlink, drm_connectors, is_displaylink,
+        BrightnessControl,
+    },
+    config::Config,
+    ddc::get_ddc_display,
+};
+
+/// Candidate brightness controls of a drm connector, along with the one in use
+pub struct ConnectorReport {
+    /// Directory of the connector in /sys/class/drm, e.g. card0-DP-1
+    pub connector: PathBuf,
+    /// Connection status reported by the kernel
+    pub status: String,
+    /// Backlight devices of the connector, the first one is preferred
+    pub backlights: Vec<PathBuf>,
+    /// i2c buses exposed by the connector, with the EDID read over each of them
+    pub i2c_devices: Vec<(String, Result<String>)>,
+    /// Target of the ddc symlink, with the EDID read over it
+    pub ddc_symlink: Option<(String, Result<String>)>,
+    /// Whether the connector belongs to a DisplayLink adapter
+    pub displaylink: bool,
+    /// Control picked by `BrightnessControl::for_device`, the same one used to get and set
+    /// the brightness, including the configuration and the cache
+    pub control: Option<Result<BrightnessControl>>,
+}
+
+/// Every step taken to find the brightness control of each drm connector, sorted by name,
+/// so that users can attach it to bug reports
+pub fn detect(config: &Config) -> Result<Vec<ConnectorReport>> {
+    Ok(drm_connectors()?
+        .into_iter()
+        .map(|connector| report(connector, config))
+        .collect())
+}
+
+fn report(connector: PathBuf, config: &Config) -> ConnectorReport {
+    let status = fs::read_to_string(connector.join("status"))
+        .unwrap_or_default()
+        .trim()
+        .to_string();
+    let i2c_devices = connector_i2c_devices(&connector)
+        .into_iter()
+        .map(|i2c_device| {
+            let edid = read_edid(&i2c_device);
+            (i2c_device, edid)
+        })
+        .collect();
+    let ddc_symlink = ddc_symlink(&connector).map(|ddc_device| match ddc_device {
+        Ok(ddc_device) => {
+            let edid = read_edid(&ddc_device);
+            (ddc_device, edid)
+        }
+        Err(err) => (String::new(), Err(err)),
+    });
+    ConnectorReport {
+        status,
+        backlights: connector_backlights(&connector),
+        i2c_devices,
+        ddc_symlink,
+        displaylink: is_displaylink(&connector),
+        control: BrightnessControl::for_device(&connector_name(&connector), config),
+        connector,
+    }
+}
+
+/// Manufacturer and model read from the EDID over the i2c device
+fn read_edid(i2c_device: &str) -> Result<String> {
+    get_ddc_display(i2c_device).map(|display| {
+        format!(
+            "{} {}",
+            display.info.manufacturer_id.as_deref().unwrap_or("unknown"),
+            display.info.model_name.as_deref().unwrap_or("unknown")
+        )
+    })
+}
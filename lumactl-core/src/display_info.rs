@@ -0,0 +1,94 @@
+use std::process::Command;
+
+use eyre::{Context, Result};
+
+use crate::{config::Config, hyprland, mock, sway};
+
+/// A display as reported by the compositor
+#[derive(serde::Deserialize)]
+pub struct DisplayInfo {
+    /// Manufacturer, empty when unknown
+    #[serde(default)]
+    pub make: String,
+    /// Model name
+    pub model: String,
+    /// Connector name, e.g. DP-1
+    pub name: String,
+    /// Human readable description, usually the make, model and connector
+    pub description: String,
+    /// Serial number from the EDID, empty when unknown
+    #[serde(default)]
+    pub serial: String,
+    /// Whether the display has the focus, only reported by Hyprland and sway
+    #[serde(default)]
+    pub focused: bool,
+    /// Whether the display is the primary one, only reported by sway
+    #[serde(default)]
+    pub primary: bool,
+}
+
+impl DisplayInfo {
+    /// The connected displays, from the mock ones, Hyprland, sway or wmctl, in this order
+    pub fn get_displays(config: &Config) -> Result<Vec<Self>> {
+        let mock_displays = mock::display_count(config);
+        if mock_displays > 0 {
+            return Ok(mock::get_displays(mock_displays));
+        }
+        // Prefer the compositor IPC when available, as it knows which output is focused
+        if hyprland::is_running() {
+            return hyprland::get_displays();
+        }
+        if sway::is_running() {
+            return sway::get_displays();
+        }
+        let outputs = String::from_utf8(
+            Command::new("wmctl")
+                .args(["list-outputs", "--json"])
+                .output()?
+                .stdout,
+        )?;
+        serde_json::from_str(&outputs).context("failed to parse wmctl output")
+    }
+
+    /// Match the display name against the display's model name, id or description
+    pub fn match_name(&self, display_name: &str) -> bool {
+        self.name.contains(display_name)
+            || self.model.contains(display_name)
+            || self.description.contains(display_name)
+    }
+
+    /// Whether the display is a laptop panel, based on the connector type
+    pub fn is_internal(&self) -> bool {
+        is_internal_connector(&self.name)
+    }
+
+    /// Make, model and description of the display, skipping the missing ones
+    pub fn details(&self) -> String {
+        [&self.make, &self.model, &self.description]
+            .into_iter()
+            .filter(|field| !field.is_empty())
+            .map(String::as_str)
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// Whether the name looks like a connector name, e.g. DP-1 or HDMI-A-1, rather than a model
+/// or description
+pub fn is_connector_name(name: &str) -> bool {
+    name.rsplit_once('-').is_some_and(|(kind, index)| {
+        !kind.is_empty()
+            && kind
+                .split('-')
+                .all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_alphabetic()))
+            && !index.is_empty()
+            && index.chars().all(|c| c.is_ascii_digit())
+    })
+}
+
+/// Whether the connector name (e.g. eDP-1) belongs to a built-in panel
+pub fn is_internal_connector(name: &str) -> bool {
+    ["eDP", "LVDS", "DSI"]
+        .iter()
+        .any(|connector| name.starts_with(connector))
+}
@@ -0,0 +1,59 @@
+use std::sync::OnceLock;
+
+use eyre::{ensure, Result};
+use tracing::{debug, instrument};
+
+use crate::busctl;
+
+const DAEMON: &str = "gsd-power";
+const SERVICE: &str = "org.gnome.SettingsDaemon.Power";
+const PATH: &str = "/org/gnome/SettingsDaemon/Power";
+const INTERFACE: &str = "org.gnome.SettingsDaemon.Power.Screen";
+/// The Screen interface exposes the brightness as a percentage
+const MAX_BRIGHTNESS: u32 = 100;
+
+/// Whether gsd-power, the GNOME power daemon, controls the screen brightness of this session.
+/// Only the GNOME releases still exposing the Screen interface are supported, on the newer
+/// ones the backlight is written directly
+pub fn is_running() -> bool {
+    static RUNNING: OnceLock<bool> = OnceLock::new();
+    *RUNNING.get_or_init(|| {
+        // Other desktops don't need to spawn busctl
+        busctl::is_desktop("GNOME")
+            && brightness()
+                .inspect_err(|err| debug!("gsd-power is not available: {err:#}"))
+                .is_ok()
+    })
+}
+
+/// Current and maximum brightness of the screen
+#[instrument(level = "debug")]
+pub fn brightness() -> Result<(u32, u32)> {
+    let reply = busctl::session(
+        DAEMON,
+        &["get-property", SERVICE, PATH, INTERFACE, "Brightness"],
+    )?;
+    let brightness = busctl::parse_int(DAEMON, &reply)?;
+    // -1 when gsd-power didn't find a backlight
+    ensure!(brightness >= 0, "gsd-power does not control any backlight");
+    Ok((brightness as u32, MAX_BRIGHTNESS))
+}
+
+/// Set the brightness of the screen
+#[instrument(level = "debug")]
+pub fn set_brightness(value: u32) -> Result<()> {
+    let value = value.min(MAX_BRIGHTNESS).to_string();
+    busctl::session(
+        DAEMON,
+        &[
+            "set-property",
+            SERVICE,
+            PATH,
+            INTERFACE,
+            "Brightness",
+            "i",
+            &value,
+        ],
+    )?;
+    Ok(())
+}
@@ -0,0 +1,60 @@
+use std::{
+    env,
+    io::{Read, Write},
+    os::unix::net::UnixStream,
+    path::PathBuf,
+};
+
+use eyre::{Context, ContextCompat, Result};
+
+use crate::display_info::DisplayInfo;
+
+#[derive(serde::Deserialize)]
+struct Monitor {
+    name: String,
+    description: String,
+    make: String,
+    model: String,
+    serial: String,
+    focused: bool,
+}
+
+/// Path of the Hyprland request socket, if Hyprland is running
+fn socket_path() -> Option<PathBuf> {
+    let signature = env::var_os("HYPRLAND_INSTANCE_SIGNATURE")?;
+    // Newer Hyprland versions put the socket in the runtime dir, older ones in /tmp
+    let runtime_dir = env::var_os("XDG_RUNTIME_DIR").map(|dir| PathBuf::from(dir).join("hypr"));
+    [runtime_dir, Some(PathBuf::from("/tmp/hypr"))]
+        .into_iter()
+        .flatten()
+        .map(|dir| dir.join(&signature).join(".socket.sock"))
+        .find(|path| path.exists())
+}
+
+pub fn is_running() -> bool {
+    socket_path().is_some()
+}
+
+/// Get the monitors from Hyprland IPC, which include the focused state
+pub fn get_displays() -> Result<Vec<DisplayInfo>> {
+    let path = socket_path().context("Hyprland is not running")?;
+    let mut stream = UnixStream::connect(&path)
+        .with_context(|| format!("failed to connect to Hyprland socket {:?}", path))?;
+    stream.write_all(b"j/monitors")?;
+    let mut reply = String::new();
+    stream.read_to_string(&mut reply)?;
+    let monitors: Vec<Monitor> =
+        serde_json::from_str(&reply).context("failed to parse Hyprland monitors")?;
+    Ok(monitors
+        .into_iter()
+        .map(|monitor| DisplayInfo {
+            make: monitor.make,
+            model: monitor.model,
+            name: monitor.name,
+            description: monitor.description,
+            serial: monitor.serial,
+            focused: monitor.focused,
+            primary: false,
+        })
+        .collect())
+}
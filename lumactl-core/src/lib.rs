@@ -0,0 +1,49 @@
+//! Control the brightness of backlights and of external displays over DDC/CI.
+//!
+//! ```no_run
+//! use lumactl_core::{
+//!     brightness_control::{BrightnessControl, Limits},
+//!     config::Config,
+//!     display_info::DisplayInfo,
+//! };
+//!
+//! # fn main() -> eyre::Result<()> {
+//! let config = Config::load()?;
+//! for display in DisplayInfo::get_displays(&config)? {
+//!     let mut br_ctl = BrightnessControl::get_from_name(&display.name, &config)?;
+//!     let (brightness, max_brightness) = br_ctl.brightness()?;
+//!     println!("{}: {brightness}/{max_brightness}", display.name);
+//!     br_ctl.set_brightness("50%", &Limits::default(), Default::default())?;
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`display_info::DisplayInfo`] lists the displays known to the compositor, while
+//! [`brightness_control::BrightnessControl`] finds and drives the backlight or DDC/CI bus of
+//! each of them. Both honor the user [`config::Config`].
+
+#![warn(missing_docs)]
+
+/// Backlight devices in /sys/class/backlight
+pub mod backlight;
+/// Finding and driving the brightness control of a display
+pub mod brightness_control;
+mod busctl;
+mod cache;
+/// User configuration, read from $XDG_CONFIG_HOME/lumactl/config.toml
+pub mod config;
+/// DDC/CI communication with external monitors
+pub mod ddc;
+/// Diagnostics of how the brightness controls are found
+pub mod detect;
+/// Displays known to the compositor
+pub mod display_info;
+mod gsd_power;
+mod hyprland;
+/// Fake displays, to try lumactl without controllable hardware
+pub mod mock;
+mod powerdevil;
+/// Saved brightness of a set of displays
+pub mod profile;
+mod sway;
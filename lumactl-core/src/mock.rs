@@ -0,0 +1,70 @@
+use std::{
+    env,
+    path::{Path, PathBuf},
+};
+
+use eyre::{Context, Result};
+
+use crate::{config::Config, display_info::DisplayInfo};
+
+const MOCK_PREFIX: &str = "MOCK-";
+const MOCK_MAX_BRIGHTNESS: u32 = 100;
+const MOCK_DEFAULT_BRIGHTNESS: u32 = 50;
+
+/// Number of fake displays to expose, from $LUMACTL_MOCK_DISPLAYS or the config
+pub fn display_count(config: &Config) -> u32 {
+    env::var("LUMACTL_MOCK_DISPLAYS")
+        .ok()
+        .and_then(|count| count.parse().ok())
+        .unwrap_or(config.mock_displays)
+}
+
+/// The fake displays, named MOCK-1, MOCK-2 and so on
+pub fn get_displays(count: u32) -> Vec<DisplayInfo> {
+    (1..=count)
+        .map(|index| DisplayInfo {
+            make: "lumactl".to_string(),
+            model: "Mock".to_string(),
+            name: format!("{MOCK_PREFIX}{index}"),
+            description: format!("lumactl Mock {index} ({MOCK_PREFIX}{index})"),
+            serial: format!("{index:08}"),
+            focused: index == 1,
+            primary: index == 1,
+        })
+        .collect()
+}
+
+/// Path of the file storing the brightness of the fake display, if `name` is one of them
+pub fn state_path(name: &str, config: &Config) -> Option<Result<PathBuf>> {
+    let index = name.strip_prefix(MOCK_PREFIX)?.parse::<u32>().ok()?;
+    if index == 0 || index > display_count(config) {
+        return None;
+    }
+    // The CLI exits after each command, keep the state in the runtime dir
+    Some(
+        xdg::BaseDirectories::with_prefix("lumactl")
+            .context("failed to get the runtime directory")
+            .and_then(|xdg_dirs| {
+                xdg_dirs
+                    .place_runtime_file(format!("mock/{name}"))
+                    .context("failed to create the mock state directory")
+            }),
+    )
+}
+
+/// Current and maximum brightness of the fake display
+pub fn mock_brightness(path: &Path) -> Result<(u32, u32)> {
+    let brightness = match std::fs::read_to_string(path) {
+        Ok(content) => content
+            .trim()
+            .parse()
+            .context("failed to parse mock state")?,
+        Err(_) => MOCK_DEFAULT_BRIGHTNESS,
+    };
+    Ok((brightness, MOCK_MAX_BRIGHTNESS))
+}
+
+/// Store the brightness of the fake display
+pub fn set_mock_brightness(path: &Path, new_br: u32) -> Result<()> {
+    std::fs::write(path, new_br.to_string()).context("failed to write mock state")
+}
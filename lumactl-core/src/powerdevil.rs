@@ -0,0 +1,47 @@
+use std::sync::OnceLock;
+
+use eyre::Result;
+use tracing::{debug, instrument};
+
+use crate::busctl;
+
+const DAEMON: &str = "powerdevil";
+const SERVICE: &str = "org.kde.Solid.PowerManagement";
+const PATH: &str = "/org/kde/Solid/PowerManagement/Actions/BrightnessControl";
+const INTERFACE: &str = "org.kde.Solid.PowerManagement.Actions.BrightnessControl";
+
+/// Whether powerdevil, the KDE power management daemon, controls the screen brightness of
+/// this session. It restores its own value after external writes to the backlight
+pub fn is_running() -> bool {
+    static RUNNING: OnceLock<bool> = OnceLock::new();
+    *RUNNING.get_or_init(|| {
+        // Other desktops don't need to spawn busctl
+        busctl::is_desktop("KDE")
+            && call("brightnessMax", &[])
+                .inspect_err(|err| debug!("powerdevil is not available: {err:#}"))
+                .is_ok()
+    })
+}
+
+/// Current and maximum brightness of the screen
+#[instrument(level = "debug")]
+pub fn brightness() -> Result<(u32, u32)> {
+    let brightness = busctl::parse_int(DAEMON, &call("brightness", &[])?)?;
+    let max_brightness = busctl::parse_int(DAEMON, &call("brightnessMax", &[])?)?;
+    Ok((brightness.try_into()?, max_brightness.try_into()?))
+}
+
+/// Set the brightness of the screen, without showing the on screen display
+#[instrument(level = "debug")]
+pub fn set_brightness(value: u32) -> Result<()> {
+    let value = i32::try_from(value)?.to_string();
+    call("setBrightnessSilent", &["i", &value])?;
+    Ok(())
+}
+
+/// Call the method of the brightness control
+fn call(method: &str, args: &[&str]) -> Result<String> {
+    let mut call = vec!["call", SERVICE, PATH, INTERFACE, method];
+    call.extend_from_slice(args);
+    busctl::session(DAEMON, &call)
+}
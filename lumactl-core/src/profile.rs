@@ -0,0 +1,47 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use eyre::{ensure, Context, ContextCompat, Result};
+
+const PROFILES_DIR: &str = "profiles";
+
+/// Brightness of a set of displays, saved to be applied later
+#[derive(Default, serde::Serialize, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Profile {
+    /// Raw brightness value of each display, keyed by its name
+    pub displays: BTreeMap<String, u32>,
+}
+
+impl Profile {
+    /// Load the profile from `$XDG_CONFIG_HOME/lumactl/profiles/<name>.toml`
+    pub fn load(name: &str) -> Result<Self> {
+        let xdg_dirs = xdg::BaseDirectories::with_prefix("lumactl")?;
+        let path = xdg_dirs
+            .find_config_file(profile_file(name)?)
+            .with_context(|| format!("profile {name} not found"))?;
+        let content =
+            fs::read_to_string(&path).with_context(|| format!("failed to read {:?}", path))?;
+        toml::from_str(&content).with_context(|| format!("failed to parse {:?}", path))
+    }
+
+    /// Save the profile to `$XDG_CONFIG_HOME/lumactl/profiles/<name>.toml`, replacing it
+    pub fn save(&self, name: &str) -> Result<PathBuf> {
+        let xdg_dirs = xdg::BaseDirectories::with_prefix("lumactl")?;
+        let path = xdg_dirs.place_config_file(profile_file(name)?)?;
+        fs::write(&path, toml::to_string(self)?)
+            .with_context(|| format!("failed to write {:?}", path))?;
+        Ok(path)
+    }
+}
+
+fn profile_file(name: &str) -> Result<PathBuf> {
+    ensure!(
+        !name.is_empty() && !name.contains('/') && !name.starts_with('.'),
+        "invalid profile name {name}"
+    );
+    Ok(Path::new(PROFILES_DIR).join(format!("{name}.toml")))
+}
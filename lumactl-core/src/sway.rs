@@ -0,0 +1,111 @@
+use std::{
+    env,
+    io::{Read, Write},
+    os::unix::net::UnixStream,
+    path::PathBuf,
+};
+
+use eyre::{ensure, Context, ContextCompat, Result};
+
+use crate::display_info::DisplayInfo;
+
+const IPC_MAGIC: &[u8] = b"i3-ipc";
+const GET_OUTPUTS: u32 = 3;
+
+#[derive(serde::Deserialize)]
+struct Output {
+    name: String,
+    make: String,
+    model: String,
+    serial: String,
+    // Disabled and non-desktop outputs lack these
+    #[serde(default)]
+    active: bool,
+    #[serde(default)]
+    focused: bool,
+    #[serde(default)]
+    primary: bool,
+}
+
+fn socket_path() -> Option<PathBuf> {
+    env::var_os("SWAYSOCK")
+        .map(PathBuf::from)
+        .filter(|path| path.exists())
+}
+
+pub fn is_running() -> bool {
+    socket_path().is_some()
+}
+
+/// Send a message using the i3-ipc protocol and return the payload of the reply
+fn request(stream: &mut UnixStream, message_type: u32, payload: &[u8]) -> Result<Vec<u8>> {
+    let mut message = IPC_MAGIC.to_vec();
+    message.extend_from_slice(&(payload.len() as u32).to_ne_bytes());
+    message.extend_from_slice(&message_type.to_ne_bytes());
+    message.extend_from_slice(payload);
+    stream.write_all(&message)?;
+
+    let mut header = [0u8; 14];
+    stream.read_exact(&mut header)?;
+    ensure!(&header[..6] == IPC_MAGIC, "invalid reply from sway");
+    let len = u32::from_ne_bytes(header[6..10].try_into()?) as usize;
+    let mut reply = vec![0u8; len];
+    stream.read_exact(&mut reply)?;
+    Ok(reply)
+}
+
+/// Get the active outputs from sway IPC, which include the focused state
+pub fn get_displays() -> Result<Vec<DisplayInfo>> {
+    let path = socket_path().context("sway is not running")?;
+    let mut stream = UnixStream::connect(&path)
+        .with_context(|| format!("failed to connect to sway socket {:?}", path))?;
+    let reply = request(&mut stream, GET_OUTPUTS, &[])?;
+    parse_outputs(&reply)
+}
+
+/// Convert the reply of GET_OUTPUTS, skipping the disabled outputs
+fn parse_outputs(reply: &[u8]) -> Result<Vec<DisplayInfo>> {
+    let outputs: Vec<Output> =
+        serde_json::from_slice(reply).context("failed to parse sway outputs")?;
+    Ok(outputs
+        .into_iter()
+        .filter(|output| output.active)
+        .map(|output| DisplayInfo {
+            // sway doesn't have a description, build it the same way wlroots does
+            description: format!(
+                "{} {} {} ({})",
+                output.make, output.model, output.serial, output.name
+            ),
+            make: output.make,
+            model: output.model,
+            name: output.name,
+            serial: output.serial,
+            focused: output.focused,
+            primary: output.primary,
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn disabled_outputs() {
+        let reply = br#"[
+            {"name": "eDP-1", "make": "BOE", "model": "0x0BCA", "serial": "Unknown",
+             "active": false, "dpms": false, "primary": false},
+            {"name": "DP-1", "make": "Dell Inc.", "model": "DELL U2720Q", "serial": "ABC123",
+             "active": true, "dpms": true, "primary": false, "focused": true}
+        ]"#;
+        let displays = parse_outputs(reply).unwrap();
+        assert_eq!(displays.len(), 1);
+        assert_eq!(displays[0].name, "DP-1");
+        assert_eq!(
+            displays[0].description,
+            "Dell Inc. DELL U2720Q ABC123 (DP-1)"
+        );
+        assert!(displays[0].focused);
+        assert!(!displays[0].primary);
+    }
+}
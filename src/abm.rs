@@ -0,0 +1,58 @@
+use std::{fs, path::PathBuf};
+
+use eyre::{bail, ensure, Context, Result};
+
+use lumactl_core::config::Config;
+
+use crate::target::{self, Target};
+
+/// Highest Adaptive Backlight Management level accepted by amdgpu
+pub const MAX_ABM_LEVEL: u8 = 4;
+
+/// The amdgpu ABM knob of each target supporting it. Only the displays passed explicitly
+/// must support it, the others are skipped
+fn abm_knobs(targets: Vec<Target>, config: &Config) -> Result<Vec<(String, PathBuf)>> {
+    let mut knobs = Vec::new();
+    for target in targets {
+        match target
+            .connector(config)
+            .map(|connector| connector.join("amdgpu/panel_power_savings"))
+        {
+            Ok(knob) if knob.exists() => knobs.push((target.name, knob)),
+            _ if target.info.is_some() => {}
+            Ok(_) => bail!(
+                "{} does not support amdgpu Adaptive Backlight Management",
+                target.name
+            ),
+            Err(err) => return Err(err),
+        }
+    }
+    ensure!(
+        !knobs.is_empty(),
+        "no display supports amdgpu Adaptive Backlight Management"
+    );
+    Ok(knobs)
+}
+
+/// Print the ABM level of the displays
+pub fn print_abm(targets: Vec<Target>, config: &Config) -> Result<()> {
+    let single = target::is_single(&targets);
+    for (name, knob) in abm_knobs(targets, config)? {
+        let level =
+            fs::read_to_string(&knob).with_context(|| format!("failed to read {:?}", knob))?;
+        if single {
+            println!("{}", level.trim());
+        } else {
+            println!("{name}: {}", level.trim());
+        }
+    }
+    Ok(())
+}
+
+pub fn set_abm(targets: Vec<Target>, config: &Config, level: u8) -> Result<()> {
+    for (_, knob) in abm_knobs(targets, config)? {
+        fs::write(&knob, level.to_string())
+            .with_context(|| format!("failed to write {:?}", knob))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,67 @@
+use eyre::Result;
+
+use lumactl_core::{
+    config::Config,
+    detect::{detect, ConnectorReport},
+};
+
+/// Print every step taken by `BrightnessControl::for_device` for each drm connector,
+/// so that users can attach it to bug reports
+pub fn print_report(config: &Config) -> Result<()> {
+    for report in detect(config)? {
+        print_connector(&report);
+        println!();
+    }
+    Ok(())
+}
+
+fn print_connector(report: &ConnectorReport) {
+    println!(
+        "{} ({})",
+        report
+            .connector
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy(),
+        report.status
+    );
+
+    if report.backlights.is_empty() {
+        println!("  backlight: none");
+    }
+    for backlight in &report.backlights {
+        println!("  backlight: {:?}", backlight);
+    }
+
+    if report.i2c_devices.is_empty() {
+        println!("  i2c: none");
+    }
+    for (i2c_device, edid) in &report.i2c_devices {
+        println!("  {i2c_device}: {}", describe_edid(edid));
+    }
+
+    match &report.ddc_symlink {
+        Some((ddc_device, edid)) => {
+            println!("  ddc symlink: {ddc_device}: {}", describe_edid(edid))
+        }
+        None => println!("  ddc symlink: none"),
+    }
+
+    if report.displaylink {
+        println!("  DisplayLink adapter (evdi)");
+    }
+
+    match &report.control {
+        Some(Ok(br_ctl)) => println!("  decision: {} {}", br_ctl.backend(), br_ctl.device()),
+        Some(Err(err)) => println!("  decision: {err:#}"),
+        None => println!("  decision: no brightness control found"),
+    }
+}
+
+/// Describe the outcome of reading the EDID over an i2c device
+fn describe_edid(edid: &Result<String>) -> String {
+    match edid {
+        Ok(details) => format!("EDID ok, {details}"),
+        Err(err) => format!("{err:#}"),
+    }
+}
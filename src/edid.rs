@@ -0,0 +1,191 @@
+use std::{fmt::Write, fs};
+
+use eyre::{ensure, Context, Result};
+
+use lumactl_core::config::Config;
+
+use crate::target::Target;
+
+/// Size of the base EDID block and of each extension block
+const BLOCK_SIZE: usize = 128;
+
+/// The EDID exposed by the drm connector of the display
+fn read_edid(target: &Target, config: &Config) -> Result<Vec<u8>> {
+    let edid_path = target.connector(config)?.join("edid");
+    let edid = fs::read(&edid_path).with_context(|| format!("failed to read {:?}", edid_path))?;
+    ensure!(
+        edid.len() >= BLOCK_SIZE,
+        "no EDID available for {}",
+        target.name
+    );
+    Ok(edid)
+}
+
+/// Describe the EDID of the display, both parsed and as hex
+pub fn format_edid(target: &Target, config: &Config) -> Result<String> {
+    let edid = read_edid(target, config)?;
+    let mut out = format!("{}:\n", target.name);
+    // The manufacturer is made of three 5 bit letters, 1 being A
+    let vendor = u16::from_be_bytes([edid[8], edid[9]]);
+    let vendor = [10, 5, 0]
+        .map(|shift| (b'A' - 1 + ((vendor >> shift) & 0x1f) as u8) as char)
+        .iter()
+        .collect::<String>();
+    writeln!(out, "  vendor: {vendor}")?;
+    if let Some(model) = descriptor(&edid, 0xfc) {
+        writeln!(out, "  model: {model}")?;
+    }
+    writeln!(
+        out,
+        "  product code: {:#06x}",
+        u16::from_le_bytes([edid[10], edid[11]])
+    )?;
+    let serial = u32::from_le_bytes([edid[12], edid[13], edid[14], edid[15]]);
+    match descriptor(&edid, 0xff) {
+        Some(serial_string) => writeln!(out, "  serial: {serial} ({serial_string})")?,
+        None => writeln!(out, "  serial: {serial}")?,
+    }
+    let (week, year) = (edid[16], 1990 + edid[17] as u32);
+    // Week 0xff means that the year is the model year
+    match week {
+        0 => writeln!(out, "  manufactured: {year}")?,
+        0xff => writeln!(out, "  model year: {year}")?,
+        week => writeln!(out, "  manufactured: week {week}, {year}")?,
+    }
+    if let Some((min, max)) = luminance_range(&edid) {
+        writeln!(out, "  luminance: {min:.4}-{max:.0} cd/m²")?;
+    }
+    writeln!(out, "  raw:")?;
+    for line in edid.chunks(16) {
+        let hex = line
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect::<String>();
+        writeln!(out, "    {hex}")?;
+    }
+    Ok(out.trim_end().to_string())
+}
+
+/// Text of the display descriptor with the given tag in the base block
+fn descriptor(edid: &[u8], tag: u8) -> Option<String> {
+    edid[54..126]
+        .chunks(18)
+        .find(|descriptor| descriptor[..3] == [0, 0, 0] && descriptor[3] == tag)
+        .map(|descriptor| {
+            String::from_utf8_lossy(&descriptor[5..])
+                .split('\n')
+                .next()
+                .unwrap_or_default()
+                .trim()
+                .to_string()
+        })
+        .filter(|text| !text.is_empty())
+}
+
+/// Minimum and maximum luminance from the HDR static metadata block of the CTA extension
+fn luminance_range(edid: &[u8]) -> Option<(f32, f32)> {
+    edid.chunks_exact(BLOCK_SIZE)
+        .skip(1)
+        // CTA-861 extension blocks
+        .filter(|block| block[0] == 0x02)
+        .find_map(|block| {
+            let end = (block[2] as usize).min(BLOCK_SIZE);
+            let mut data_blocks = &block[4.min(end)..end];
+            while let Some((&header, rest)) = data_blocks.split_first() {
+                let len = (header & 0x1f) as usize;
+                let payload = rest.get(..len)?;
+                // Extended tag 6 is the HDR static metadata data block
+                if header >> 5 == 7 && payload.first() == Some(&6) && len >= 6 {
+                    let max = 50.0 * 2f32.powf(payload[3] as f32 / 32.0);
+                    let min = max * (payload[5] as f32 / 255.0).powi(2) / 100.0;
+                    return Some((min, max));
+                }
+                data_blocks = &rest[len..];
+            }
+            None
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Base block with the model and serial descriptors, followed by a CTA-861 extension
+    /// holding a video data block and the HDR static metadata block
+    fn edid_with_hdr(max_luminance: u8, min_luminance: u8) -> Vec<u8> {
+        let mut edid = vec![0u8; BLOCK_SIZE * 2];
+        edid[..8].copy_from_slice(&[0x00, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x00]);
+        // The first descriptor is a detailed timing, the text ones follow
+        edid[54] = 0x01;
+        edid[72..77].copy_from_slice(&[0, 0, 0, 0xfc, 0]);
+        edid[77..90].copy_from_slice(b"LUMA 27\n     ");
+        edid[90..95].copy_from_slice(&[0, 0, 0, 0xff, 0]);
+        edid[95..108].copy_from_slice(b"ABC123\n      ");
+        edid[126] = 1;
+
+        let cta = &mut edid[BLOCK_SIZE..];
+        cta[..4].copy_from_slice(&[0x02, 0x03, 0, 0]);
+        let data_blocks = [
+            // Video data block with two VICs
+            0x42,
+            16,
+            31,
+            // HDR static metadata: extended tag 6, EOTFs, metadata type and luminances
+            0xe6,
+            0x06,
+            0x05,
+            0x01,
+            max_luminance,
+            0,
+            min_luminance,
+        ];
+        cta[4..4 + data_blocks.len()].copy_from_slice(&data_blocks);
+        cta[2] = (4 + data_blocks.len()) as u8;
+        edid
+    }
+
+    #[test]
+    fn text_descriptors() {
+        let edid = edid_with_hdr(96, 0);
+        assert_eq!(descriptor(&edid, 0xfc).as_deref(), Some("LUMA 27"));
+        assert_eq!(descriptor(&edid, 0xff).as_deref(), Some("ABC123"));
+        assert_eq!(descriptor(&edid, 0xfe), None);
+    }
+
+    #[test]
+    fn empty_descriptor() {
+        let mut edid = edid_with_hdr(96, 0);
+        edid[77..90].copy_from_slice(b"\n            ");
+        assert_eq!(descriptor(&edid, 0xfc), None);
+    }
+
+    #[test]
+    fn hdr_luminance() {
+        // 50 * 2^(96 / 32) = 400 cd/m²
+        let (min, max) = luminance_range(&edid_with_hdr(96, 0)).unwrap();
+        assert_eq!(max, 400.0);
+        assert_eq!(min, 0.0);
+        // The minimum is max * (cv / 255)^2 / 100
+        let (min, max) = luminance_range(&edid_with_hdr(128, 255)).unwrap();
+        assert_eq!(max, 800.0);
+        assert_eq!(min, 8.0);
+    }
+
+    #[test]
+    fn no_hdr_metadata() {
+        let edid = edid_with_hdr(96, 0);
+        assert_eq!(luminance_range(&edid[..BLOCK_SIZE]), None);
+        // A CTA extension with only the video data block
+        let mut edid = edid;
+        edid[BLOCK_SIZE + 2] = 7;
+        assert_eq!(luminance_range(&edid), None);
+    }
+
+    #[test]
+    fn truncated_data_block() {
+        let mut edid = edid_with_hdr(96, 0);
+        // The video data block claims to be longer than the data block collection
+        edid[BLOCK_SIZE + 4] = 0x5f;
+        assert_eq!(luminance_range(&edid), None);
+    }
+}
@@ -0,0 +1,78 @@
+use std::fs;
+
+use eyre::Result;
+
+use lumactl_core::{
+    brightness_control::{connector_name, drm_connectors},
+    config::Config,
+    mock,
+};
+
+use crate::{
+    format_bar,
+    target::{self, Target},
+};
+
+/// Print every connected display, along with the brightness control that would be used
+/// and whether it currently works. When `pretty`, the brightness of the working displays
+/// is drawn as a bar and the errors are red
+pub fn print_list(config: &Config, pretty: bool) -> Result<()> {
+    let targets = connected_displays(config)?
+        .into_iter()
+        .map(|name| Target { name, info: None })
+        .collect::<Vec<_>>();
+    let name_width = targets.iter().map(|t| t.name.len()).max().unwrap_or(0);
+    let results = target::run_parallel(targets, |target| {
+        let mut br_ctl = target.brightness_control(config)?;
+        let status = match br_ctl.brightness() {
+            Ok((brightness, max_brightness)) if pretty => format_bar(brightness, max_brightness),
+            Ok(_) => "ok".to_string(),
+            Err(err) => format_error(&err, pretty),
+        };
+        Ok((br_ctl.backend(), br_ctl.device(), status))
+    });
+    let rows = results
+        .into_iter()
+        .map(|(name, res)| {
+            let (backend, device, status) =
+                res.unwrap_or_else(|err| ("none", "-".to_string(), format_error(&err, pretty)));
+            (name, backend, device, status)
+        })
+        .collect::<Vec<_>>();
+    let backend_width = rows
+        .iter()
+        .map(|(_, backend, _, _)| backend.len())
+        .max()
+        .unwrap_or(0);
+    for (name, backend, device, status) in rows {
+        println!("{name:<name_width$}  {backend:<backend_width$}  {device}  {status}");
+    }
+    Ok(())
+}
+
+fn format_error(err: &eyre::Error, pretty: bool) -> String {
+    if pretty {
+        format!("\x1b[31m{err:#}\x1b[0m")
+    } else {
+        format!("{err:#}")
+    }
+}
+
+/// Name of the connected drm connectors, or of the mock displays when enabled
+fn connected_displays(config: &Config) -> Result<Vec<String>> {
+    let mock_displays = mock::display_count(config);
+    if mock_displays > 0 {
+        return Ok(mock::get_displays(mock_displays)
+            .into_iter()
+            .map(|display| display.name)
+            .collect());
+    }
+    Ok(drm_connectors()?
+        .into_iter()
+        .filter(|connector| {
+            fs::read_to_string(connector.join("status"))
+                .is_ok_and(|status| status.trim() == "connected")
+        })
+        .map(|connector| connector_name(&connector))
+        .collect())
+}
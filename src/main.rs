@@ -1,20 +1,35 @@
-mod backlight;
-mod brightness_control;
-mod ddc;
-mod display_info;
+mod abm;
+mod detect;
+mod edid;
+mod list;
+mod target;
+
+use std::{
+    env,
+    io::{self, IsTerminal},
+    os::unix::process::CommandExt,
+    process::Command,
+    time::Duration,
+};
 
-use brightness_control::BrightnessControl;
 use clap::Parser;
 use clap::Subcommand;
-use ddc::ddc_brightness;
-use ddc::get_ddc_display;
-use ddc::set_ddc_brightness;
-use display_info::DisplayInfo;
 use eyre::bail;
-use eyre::ensure;
 use eyre::Context;
 use eyre::ContextCompat;
 use eyre::Result;
+use lumactl_core::brightness_control::{
+    calculate_new_brightness, BrightnessControl, Feature, Limits, PowerState,
+};
+use lumactl_core::config::Config;
+use lumactl_core::display_info::DisplayInfo;
+use lumactl_core::profile::Profile;
+use target::{Target, TargetArgs};
+use tracing::Level;
+use tracing_subscriber::fmt::format::FmtSpan;
+
+/// Highest number of decimals of the percentages, finer than any backlight step
+const MAX_PRECISION: u8 = 6;
 
 #[derive(Parser)]
 #[command(name = "lumactl")]
@@ -26,162 +41,653 @@ struct Args {
     cmd: Subcmd,
     #[clap(long, short, help = "Enable verbose logging")]
     verbose: bool,
+    #[clap(
+        long,
+        global = true,
+        value_parser = parse_vcp,
+        help = "VCP feature code used for the brightness of DDC displays, e.g. 0x13"
+    )]
+    vcp: Option<u8>,
 }
 
 #[derive(Debug, Subcommand, Clone)]
 enum Subcmd {
     #[clap(about = "Get the brightness of one or all displays")]
     Get {
+        #[clap(flatten)]
+        targets: TargetArgs,
+        #[clap(long, short, help = "Output the brightness as a percentage")]
+        percentage: bool,
+        #[clap(
+            long,
+            requires = "percentage",
+            value_parser = clap::value_parser!(u8).range(0..=MAX_PRECISION as i64),
+            help = "Number of decimals of the percentage, from 0 to 6, 0 if not provided"
+        )]
+        precision: Option<u8>,
         #[clap(
             long,
             short,
-            help = "The display to get the brightness of (all displays if not provided)"
+            help = "Include the display name, model and description in the output"
         )]
-        display: Option<String>,
-        #[clap(long, short, help = "Output the brightness as a percentage")]
-        percentage: bool,
+        long: bool,
+        #[clap(
+            long,
+            help = "Draw a colored bar for each display, only when the output is a terminal"
+        )]
+        pretty: bool,
+        #[clap(
+            long,
+            conflicts_with_all = ["percentage", "pretty"],
+            help = "Output the name, make, model, description, brightness, maximum and \
+                    percentage of each display as JSON"
+        )]
+        json: bool,
     },
-    #[clap(about = "Get the brightness of one or all displays")]
+    #[clap(about = "Set the brightness of one or all displays")]
     Set {
+        #[clap(flatten)]
+        targets: TargetArgs,
         #[clap(
-            long,
-            short,
-            help = "The display to set the brightness of (all displays if not provided)"
+            allow_hyphen_values = true,
+            help = "The brightness to set, a bare + or - uses the configured step"
         )]
-        display: Option<String>,
-        #[clap(help = "The brightness to set")]
         brightness: String,
+        #[clap(
+            long,
+            visible_alias = "fade",
+            value_parser = parse_duration,
+            help = "Gradually change the brightness over this duration, e.g. 500ms or 1.5s"
+        )]
+        duration: Option<Duration>,
+    },
+    #[clap(about = "Increase the brightness of one or all displays")]
+    Up {
+        #[clap(flatten)]
+        targets: TargetArgs,
+        #[clap(help = "The amount to increase (the configured step if not provided)")]
+        amount: Option<String>,
+    },
+    #[clap(about = "Decrease the brightness of one or all displays")]
+    Down {
+        #[clap(flatten)]
+        targets: TargetArgs,
+        #[clap(help = "The amount to decrease (the configured step if not provided)")]
+        amount: Option<String>,
+    },
+    #[clap(about = "Switch between the low and high brightness levels set in the config")]
+    Toggle {
+        #[clap(flatten)]
+        targets: TargetArgs,
+    },
+    #[clap(about = "Turn one or all displays on, off or in standby")]
+    Power {
+        #[clap(flatten)]
+        targets: TargetArgs,
+        #[clap(help = "The power state to set")]
+        state: PowerState,
+    },
+    #[clap(about = "Save the brightness of the displays and apply it later")]
+    Profile {
+        #[clap(subcommand)]
+        cmd: ProfileCmd,
+    },
+    #[clap(about = "Control the contrast of DDC/CI displays")]
+    Contrast {
+        #[clap(subcommand)]
+        cmd: ContrastCmd,
+    },
+    #[clap(about = "Control the color temperature of DDC/CI displays")]
+    Temperature {
+        #[clap(subcommand)]
+        cmd: TemperatureCmd,
+    },
+    #[clap(about = "List the connected displays and how their brightness is controlled")]
+    List {
+        #[clap(
+            long,
+            help = "Draw the brightness of the working displays as a bar and color the errors, \
+                    only when the output is a terminal"
+        )]
+        pretty: bool,
+    },
+    #[clap(about = "Print how the brightness control of each display is detected")]
+    Detect,
+    #[clap(about = "Print the raw and parsed EDID of one or all displays")]
+    Edid {
+        #[clap(flatten)]
+        targets: TargetArgs,
+    },
+    #[clap(about = "Control amdgpu Adaptive Backlight Management of the internal panel")]
+    Abm {
+        #[clap(subcommand)]
+        cmd: AbmCmd,
+    },
+    /// Any other subcommand runs lumactl-<subcommand> from PATH, if it exists
+    #[command(external_subcommand)]
+    External(Vec<String>),
+}
+
+#[derive(Debug, Subcommand, Clone)]
+enum ProfileCmd {
+    #[clap(about = "Save the current brightness of one or all displays in a profile")]
+    Save {
+        #[clap(flatten)]
+        targets: TargetArgs,
+        #[clap(help = "The name of the profile")]
+        name: String,
+    },
+    #[clap(about = "Set the brightness of the displays saved in a profile")]
+    Apply {
+        #[clap(help = "The name of the profile")]
+        name: String,
     },
 }
 
-/// Calculate the new brightness value based on the current brightness value
-/// We need &mut self because Display::brightness will be called
-fn calculate_new_brightness(current_brightness: (u32, u32), new_brightness: &str) -> Result<u32> {
-    // If the brightness string start with a '-' it means relative decrease
-    // If the brightness string start with a '+' it means relative increase
-    // If the brightness string is a number it means absolute value
-    // If the brightness ends with a '%' it means percentage
-    // Apply brightness reletive increase/decrease with percentage as well
+#[derive(Debug, Subcommand, Clone)]
+enum ContrastCmd {
+    #[clap(about = "Get the contrast of one or all displays")]
+    Get {
+        #[clap(flatten)]
+        targets: TargetArgs,
+        #[clap(long, short, help = "Output the contrast as a percentage")]
+        percentage: bool,
+    },
+    #[clap(about = "Set the contrast of one or all displays")]
+    Set {
+        #[clap(flatten)]
+        targets: TargetArgs,
+        #[clap(
+            allow_hyphen_values = true,
+            help = "The contrast value, absolute, relative (+/-) or a percentage, like the brightness"
+        )]
+        contrast: String,
+    },
+}
 
-    let brightness = new_brightness.trim();
-    ensure!(!brightness.is_empty(), "brightness cannot be empty");
-    let first_char = brightness.chars().next().unwrap();
-    let (br, max_br) = current_brightness;
-    let mut new_br = if first_char == '+' || first_char == '-' {
-        &brightness[1..]
-    } else {
-        brightness
-    };
-    ensure!(!new_br.is_empty(), "invalid brightness value");
-    let percentage = if new_br.ends_with('%') {
-        new_br = &new_br[..new_br.len() - 1];
-        true
-    } else {
-        false
-    };
-    let new_br = new_br.parse::<u32>().context("invalid brightness value")?;
-    // if the value provided is a percentage, calculate the absolute value with
-    // new_br * max_br / 100
-    let set_val = if percentage {
-        (new_br as f32 * max_br as f32 / 100.0) as u32
-    } else {
-        new_br
-    };
-    let new_br = match first_char {
-        '+' => {
-            // We do not want to overflow the brightness value
-            br.saturating_add(set_val)
-        }
-        '-' => br.saturating_sub(set_val),
-        _ => set_val,
+#[derive(Debug, Subcommand, Clone)]
+enum TemperatureCmd {
+    #[clap(about = "Get the color temperature of one or all displays")]
+    Get {
+        #[clap(flatten)]
+        targets: TargetArgs,
+    },
+    #[clap(about = "Set the color temperature of one or all displays")]
+    Set {
+        #[clap(flatten)]
+        targets: TargetArgs,
+        #[clap(help = "The color temperature in Kelvin, e.g. 6500")]
+        kelvin: u32,
+    },
+}
+
+#[derive(Debug, Subcommand, Clone)]
+enum AbmCmd {
+    #[clap(about = "Get the ABM level")]
+    Get {
+        #[clap(flatten)]
+        targets: TargetArgs,
+    },
+    #[clap(about = "Set the ABM level, 0 disables it")]
+    Set {
+        #[clap(flatten)]
+        targets: TargetArgs,
+        #[clap(
+            value_parser = clap::value_parser!(u8).range(0..=abm::MAX_ABM_LEVEL as i64),
+            help = "The ABM level, from 0 to 4"
+        )]
+        level: u8,
+    },
+}
+
+/// Parse a duration in milliseconds (500ms) or seconds (1.5s), plain numbers are milliseconds
+fn parse_duration(duration: &str) -> Result<Duration> {
+    let duration = duration.trim();
+    let (value, unit) = match duration.strip_suffix("ms") {
+        Some(value) => (value, 0.001),
+        None => match duration.strip_suffix('s') {
+            Some(value) => (value, 1.0),
+            None => (duration, 0.001),
+        },
     };
+    value
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .and_then(|value| Duration::try_from_secs_f64(value * unit).ok())
+        .with_context(|| format!("invalid duration {duration}"))
+}
 
-    // Apply max allowed values
-    Ok(new_br.min(max_br))
+/// Parse a VCP feature code, either hexadecimal with the 0x prefix or decimal
+fn parse_vcp(vcp: &str) -> Result<u8> {
+    match vcp.strip_prefix("0x").or_else(|| vcp.strip_prefix("0X")) {
+        Some(hex) => u8::from_str_radix(hex, 16),
+        None => vcp.parse(),
+    }
+    .with_context(|| format!("invalid VCP feature code {vcp}"))
 }
 
 fn main() -> Result<()> {
     let args = Args::parse();
+    init_logging(args.verbose);
+    let mut config = Config::load()?;
+    if let Some(vcp) = args.vcp {
+        config.override_brightness_vcp(vcp);
+    }
 
     match args.cmd {
         Subcmd::Get {
-            display,
+            targets,
             percentage,
+            precision,
+            long,
+            pretty,
+            json,
         } => {
-            if let Some(display_name) = display {
-                let mut br_ctl = BrightnessControl::get_from_name(&display_name)?;
-                match br_ctl.brightness() {
-                    Ok((brightness, max_brightness)) => {
-                        println!(
-                            "{}",
-                            format_brightness(brightness, max_brightness, percentage)
-                        );
-                    }
-                    Err(err) => eprintln!("{err:?}"),
-                }
-            } else {
-                let displays = DisplayInfo::get_displays()?;
-                displays.into_iter().for_each(|display| {
-                    let res = BrightnessControl::for_device(&display.name)
-                        .with_context(|| {
-                            format!("unable to find brightness control for {}", display.name)
-                        })
-                        .and_then(|br_ctl| {
-                            br_ctl.and_then(|mut br_ctl| {
-                                br_ctl.brightness().map(|(brightness, max_brightness)| {
-                                    println!(
-                                        "{}: {}",
-                                        display.name,
-                                        format_brightness(brightness, max_brightness, percentage)
-                                    );
-                                })
-                            })
-                        });
-
-                    match res {
-                        Ok(_) => {}
-                        Err(err) => eprintln!("{err:?}"),
-                    }
-                });
+            if json {
+                print_json(targets, &config)?;
+                return Ok(());
             }
+            let targets = targets.resolve(&config)?;
+            let single = target::is_single(&targets);
+            let pretty = pretty && io::stdout().is_terminal();
+            let name_width = targets.iter().map(|t| t.name.len()).max().unwrap_or(0);
+            let get_line = |target: Target| {
+                let mut br_ctl = target.brightness_control(&config)?;
+                let (brightness, max_brightness) = br_ctl.brightness()?;
+                let value = if pretty {
+                    format_bar(brightness, max_brightness)
+                } else {
+                    format_brightness(
+                        brightness,
+                        max_brightness,
+                        percentage.then_some(precision.unwrap_or(0) as usize),
+                    )
+                };
+                Ok(if long {
+                    // wmctl might not be available, in that case only the EDID is used
+                    let display = target.info.or_else(|| {
+                        DisplayInfo::get_displays(&config)
+                            .ok()
+                            .and_then(|displays| {
+                                displays.into_iter().find(|d| d.match_name(&target.name))
+                            })
+                    });
+                    let name = display.as_ref().map_or(&target.name, |d| &d.name);
+                    format_long(name, &value, display.as_ref(), &br_ctl)
+                } else if single {
+                    value
+                } else if pretty {
+                    let name = format!("{}:", target.name);
+                    format!("{name:<width$} {value}", width = name_width + 1)
+                } else {
+                    format!("{}: {}", target.name, value)
+                })
+            };
+            run_on_targets(targets, get_line, |_, line, _| println!("{line}"))?;
         }
         Subcmd::Set {
-            display,
+            targets,
             brightness,
-        } => {
-            if let Some(display_name) = display {
-                let mut br_ctl = BrightnessControl::get_from_name(&display_name)?;
-                match br_ctl.set_brightness(brightness.as_str()) {
-                    Ok(_) => {}
-                    Err(err) => eprintln!("{err:?}"),
-                }
-            } else {
-                let displays = DisplayInfo::get_displays()?;
-                displays.into_iter().for_each(|display| {
-                    let res = BrightnessControl::for_device(&display.name)
-                        .with_context(|| {
-                            format!("unable to find brightness control for {}", display.name)
-                        })
-                        .and_then(|br_ctl| {
-                            br_ctl.and_then(|mut br_ctl| br_ctl.set_brightness(&brightness))
-                        });
-
-                    match res {
-                        Ok(_) => {}
-                        Err(err) => eprintln!("{err:?}"),
-                    }
-                });
+            duration,
+        } => set(targets, &brightness, duration.unwrap_or_default(), &config)?,
+        Subcmd::Up { targets, amount } => set(
+            targets,
+            &format!("+{}", amount.unwrap_or_default()),
+            Duration::ZERO,
+            &config,
+        )?,
+        Subcmd::Down { targets, amount } => set(
+            targets,
+            &format!("-{}", amount.unwrap_or_default()),
+            Duration::ZERO,
+            &config,
+        )?,
+        Subcmd::Toggle { targets } => toggle(targets, &config)?,
+        Subcmd::Power { targets, state } => run_on_targets(
+            targets.resolve(&config)?,
+            |target| target.brightness_control(&config)?.set_power(state),
+            |_, (), _| (),
+        )?,
+        Subcmd::Profile { cmd } => match cmd {
+            ProfileCmd::Save { targets, name } => save_profile(targets, &name, &config)?,
+            ProfileCmd::Apply { name } => apply_profile(&name, &config)?,
+        },
+        Subcmd::Contrast { cmd } => match cmd {
+            ContrastCmd::Get {
+                targets,
+                percentage,
+            } => run_on_targets(
+                targets.resolve(&config)?,
+                |target| target.brightness_control(&config)?.get(Feature::Contrast),
+                |name, (contrast, max_contrast), single| {
+                    let contrast =
+                        format_brightness(contrast, max_contrast, percentage.then_some(0));
+                    print_value(name, &contrast, single);
+                },
+            )?,
+            ContrastCmd::Set { targets, contrast } => run_on_targets(
+                targets.resolve(&config)?,
+                |target| {
+                    target.brightness_control(&config)?.set(
+                        Feature::Contrast,
+                        &contrast,
+                        &Limits::default(),
+                        Duration::ZERO,
+                    )
+                },
+                |_, (), _| (),
+            )?,
+        },
+        Subcmd::Temperature { cmd } => match cmd {
+            TemperatureCmd::Get { targets } => run_on_targets(
+                targets.resolve(&config)?,
+                |target| target.brightness_control(&config)?.temperature(),
+                |name, kelvin, single| print_value(name, &format!("{kelvin}K"), single),
+            )?,
+            TemperatureCmd::Set { targets, kelvin } => run_on_targets(
+                targets.resolve(&config)?,
+                |target| target.brightness_control(&config)?.set_temperature(kelvin),
+                |_, (), _| (),
+            )?,
+        },
+        Subcmd::List { pretty } => list::print_list(&config, pretty && io::stdout().is_terminal())?,
+        Subcmd::Detect => detect::print_report(&config)?,
+        Subcmd::Edid { targets } => {
+            let targets = targets.resolve(&config)?;
+            let single = target::is_single(&targets);
+            for target in targets {
+                let res = edid::format_edid(&target, &config).map(|edid| println!("{edid}"));
+                report_error(res, &target.name, single)?;
             }
         }
+        Subcmd::Abm { cmd } => match cmd {
+            AbmCmd::Get { targets } => abm::print_abm(targets.resolve(&config)?, &config)?,
+            AbmCmd::Set { targets, level } => {
+                abm::set_abm(targets.resolve(&config)?, &config, level)?
+            }
+        },
+        Subcmd::External(args) => run_external(args)?,
     };
 
     Ok(())
 }
 
-fn format_brightness(brightness: u32, max_brightness: u32, percentage: bool) -> String {
-    if percentage {
-        format!("{:.0}%", brightness as f32 / max_brightness as f32 * 100.0)
+#[derive(serde::Serialize)]
+struct DisplayBrightness {
+    name: String,
+    make: Option<String>,
+    model: Option<String>,
+    description: Option<String>,
+    brightness: u32,
+    max_brightness: u32,
+    /// Not rounded, to keep the resolution of backlights with thousands of steps
+    percentage: f64,
+}
+
+/// Print the brightness of the displays as a JSON array, the failing ones are skipped
+fn print_json(targets: TargetArgs, config: &Config) -> Result<()> {
+    let targets = targets.resolve(config)?;
+    // Displays passed by name have no information from the compositor attached
+    let displays = if targets.iter().any(|target| target.info.is_none()) {
+        DisplayInfo::get_displays(config).unwrap_or_default()
     } else {
-        format!("{}/{}", brightness, max_brightness)
+        Vec::new()
+    };
+    let get_display = |target: Target| {
+        let (brightness, max_brightness) = target.brightness_control(config)?.brightness()?;
+        let info = target.info.as_ref().or_else(|| {
+            displays
+                .iter()
+                .find(|display| display.match_name(&target.name))
+        });
+        let field = |value: &String| (!value.is_empty()).then(|| value.clone());
+        Ok(DisplayBrightness {
+            make: info.and_then(|info| field(&info.make)),
+            model: info.and_then(|info| field(&info.model)),
+            description: info.and_then(|info| field(&info.description)),
+            percentage: if max_brightness == 0 {
+                0.0
+            } else {
+                brightness as f64 / max_brightness as f64 * 100.0
+            },
+            name: target.name,
+            brightness,
+            max_brightness,
+        })
+    };
+    let mut results = Vec::new();
+    run_on_targets(targets, get_display, |_, display, _| results.push(display))?;
+    println!("{}", serde_json::to_string(&results)?);
+    Ok(())
+}
+
+fn set(targets: TargetArgs, brightness: &str, fade: Duration, config: &Config) -> Result<()> {
+    let night_max = config.brightness_cap()?;
+    let set_brightness = |target: Target| {
+        let display_config = target.display_config(config);
+        let brightness = config.apply_step(brightness, display_config);
+        target.brightness_control(config)?.set_brightness(
+            &brightness,
+            &Limits::new(display_config, night_max),
+            fade,
+        )
+    };
+    run_on_targets(targets.resolve(config)?, set_brightness, |_, (), _| ())
+}
+
+/// Dim the displays brighter than their low level, bring the others to their high level
+fn toggle(targets: TargetArgs, config: &Config) -> Result<()> {
+    let night_max = config.brightness_cap()?;
+    let toggle_brightness = |target: Target| {
+        let display_config = target.display_config(config);
+        let (low, high) = config.toggle_levels(display_config);
+        let mut br_ctl = target.brightness_control(config)?;
+        let current_brightness = br_ctl.brightness()?;
+        let low_brightness =
+            calculate_new_brightness(current_brightness, low).context("invalid low level")?;
+        let new_br = if current_brightness.0 > low_brightness {
+            low
+        } else {
+            high
+        };
+        br_ctl.set_brightness(
+            new_br,
+            &Limits::new(display_config, night_max),
+            Duration::ZERO,
+        )
+    };
+    run_on_targets(targets.resolve(config)?, toggle_brightness, |_, (), _| ())
+}
+
+fn save_profile(targets: TargetArgs, name: &str, config: &Config) -> Result<()> {
+    let mut profile = Profile::default();
+    run_on_targets(
+        targets.resolve(config)?,
+        |target| Ok(target.brightness_control(config)?.brightness()?.0),
+        |name, brightness, _| {
+            profile.displays.insert(name.to_string(), brightness);
+        },
+    )?;
+    profile.save(name)?;
+    Ok(())
+}
+
+fn apply_profile(name: &str, config: &Config) -> Result<()> {
+    let profile = Profile::load(name)?;
+    let night_max = config.brightness_cap()?;
+    let targets = profile
+        .displays
+        .keys()
+        .map(|name| Target {
+            name: name.clone(),
+            info: None,
+        })
+        .collect();
+    let apply_brightness = |target: Target| {
+        let brightness = profile.displays[&target.name].to_string();
+        let limits = Limits::new(target.display_config(config), night_max);
+        target
+            .brightness_control(config)?
+            .set_brightness(&brightness, &limits, Duration::ZERO)
+    };
+    run_on_targets(targets, apply_brightness, |_, (), _| ())
+}
+
+/// Replace the current process with the lumactl-<subcommand> executable, like git and cargo do.
+/// $LUMACTL points to this executable, so that plugins can call back into it
+fn run_external(args: Vec<String>) -> Result<()> {
+    let (subcommand, args) = args.split_first().context("missing subcommand")?;
+    let mut command = Command::new(format!("lumactl-{subcommand}"));
+    command.args(args);
+    if let Ok(exe) = env::current_exe() {
+        command.env("LUMACTL", exe);
+    }
+    let err = command.exec();
+    if err.kind() == io::ErrorKind::NotFound {
+        bail!("unrecognized subcommand '{subcommand}'");
+    }
+    Err(err).with_context(|| format!("failed to run lumactl-{subcommand}"))
+}
+
+/// Run `op` on every target concurrently, then pass each result to `output` in the same
+/// order, along with whether a single display was selected. Errors go to `report_error`
+fn run_on_targets<T: Send>(
+    targets: Vec<Target>,
+    op: impl Fn(Target) -> Result<T> + Sync,
+    mut output: impl FnMut(&str, T, bool),
+) -> Result<()> {
+    let single = target::is_single(&targets);
+    for (name, res) in target::run_parallel(targets, op) {
+        report_error(res.map(|value| output(&name, value, single)), &name, single)?;
+    }
+    Ok(())
+}
+
+/// Print the value alone when a single display was selected, otherwise prefixed by its name
+fn print_value(name: &str, value: &str, single: bool) {
+    if single {
+        println!("{value}");
+    } else {
+        println!("{name}: {value}");
+    }
+}
+
+/// Errors are returned when a single display has been selected, otherwise they are printed
+/// and the remaining displays are processed
+fn report_error(res: Result<()>, name: &str, single: bool) -> Result<()> {
+    match res {
+        Ok(()) => Ok(()),
+        Err(err) if single => Err(err),
+        Err(err) => {
+            eprintln!("{name}: {err:?}");
+            Ok(())
+        }
+    }
+}
+
+/// Log to stderr, including the duration of each hardware operation when verbose
+fn init_logging(verbose: bool) {
+    let level = if verbose { Level::DEBUG } else { Level::WARN };
+    tracing_subscriber::fmt()
+        .with_max_level(level)
+        .with_span_events(FmtSpan::CLOSE)
+        .with_writer(std::io::stderr)
+        .init();
+}
+
+/// Format the brightness as a percentage with the given number of decimals, or as
+/// brightness/max when `precision` is None
+fn format_brightness(brightness: u32, max_brightness: u32, precision: Option<usize>) -> String {
+    match precision {
+        Some(precision) => format!(
+            "{:.precision$}%",
+            round_percentage(brightness, max_brightness, precision)
+        ),
+        None => format!("{}/{}", brightness, max_brightness),
+    }
+}
+
+/// Percentage of the brightness rounded half away from zero, the same way in every output
+fn round_percentage(brightness: u32, max_brightness: u32, precision: usize) -> f64 {
+    let scale = 10f64.powi(precision as i32);
+    (brightness as f64 / max_brightness as f64 * 100.0 * scale).round() / scale
+}
+
+/// Draw the brightness as a bar, colored by level, followed by the percentage
+fn format_bar(brightness: u32, max_brightness: u32) -> String {
+    const BAR_WIDTH: u64 = 20;
+    let percentage = round_percentage(brightness, max_brightness, 0);
+    let filled = (brightness as u64 * BAR_WIDTH)
+        .checked_div(max_brightness as u64)
+        .unwrap_or(0)
+        .min(BAR_WIDTH);
+    // Blue when dim, yellow in the middle and bright yellow when bright
+    let color = match percentage as u32 {
+        0..=33 => 34,
+        34..=66 => 33,
+        _ => 93,
+    };
+    format!(
+        "\x1b[{color}m{}\x1b[2m{}\x1b[0m {percentage:>3.0}%",
+        "█".repeat(filled as usize),
+        "░".repeat((BAR_WIDTH - filled) as usize)
+    )
+}
+
+/// Format the brightness value together with the model and description of the display.
+/// The information reported by wmctl is preferred, falling back to the EDID for DDC displays
+fn format_long(
+    name: &str,
+    value: &str,
+    display: Option<&DisplayInfo>,
+    br_ctl: &BrightnessControl,
+) -> String {
+    let details = display
+        .map(DisplayInfo::details)
+        .filter(|details| !details.is_empty())
+        .or_else(|| br_ctl.edid_details());
+    match details {
+        Some(details) => format!("{name}: {value} ({details})"),
+        None => format!("{name}: {value}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn durations() {
+        assert_eq!(parse_duration("500").unwrap(), Duration::from_millis(500));
+        assert_eq!(parse_duration("500ms").unwrap(), Duration::from_millis(500));
+        assert_eq!(parse_duration("2s").unwrap(), Duration::from_secs(2));
+        assert_eq!(
+            parse_duration(" 1.5s ").unwrap(),
+            Duration::from_millis(1500)
+        );
+        assert_eq!(parse_duration("0").unwrap(), Duration::ZERO);
+    }
+
+    #[test]
+    fn invalid_durations() {
+        for duration in ["", "s", "ms", "-1s", "nan", "infs", "1e400s", "2m", "fast"] {
+            assert!(
+                parse_duration(duration).is_err(),
+                "{duration} should be rejected"
+            );
+        }
+        // Too big for a Duration, instead of overflowing
+        assert!(parse_duration("99999999999999999999999s").is_err());
+    }
+
+    #[test]
+    fn vcp_codes() {
+        assert_eq!(parse_vcp("0x13").unwrap(), 0x13);
+        assert_eq!(parse_vcp("0XE0").unwrap(), 0xe0);
+        assert_eq!(parse_vcp("16").unwrap(), 0x10);
+        for vcp in ["", "0x", "0x100", "256", "-1", "ten"] {
+            assert!(parse_vcp(vcp).is_err(), "{vcp} should be rejected");
+        }
     }
 }
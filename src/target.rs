@@ -0,0 +1,239 @@
+use std::{path::PathBuf, thread};
+
+use eyre::{bail, eyre, ContextCompat, Result};
+
+use lumactl_core::{
+    brightness_control::{connector_name, drm_connectors, BrightnessControl},
+    config::{Config, DisplayConfig},
+    display_info::DisplayInfo,
+};
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct TargetArgs {
+    #[clap(
+        long,
+        short,
+        help = "The display to use, can be repeated (all displays if not provided)"
+    )]
+    display: Vec<String>,
+    #[clap(
+        long,
+        short,
+        help = "The group of displays defined in the config to use, can be repeated"
+    )]
+    group: Vec<String>,
+    #[clap(
+        long,
+        short,
+        conflicts_with_all = ["display", "group"],
+        help = "Use the focused display"
+    )]
+    focused: bool,
+    #[clap(
+        long,
+        short,
+        conflicts_with_all = ["display", "group", "focused"],
+        help = "Use all displays, even if a default display is configured"
+    )]
+    all: bool,
+    #[clap(
+        long,
+        conflicts_with_all = ["display", "group", "focused", "all"],
+        help = "Use the internal panels (eDP, LVDS and DSI connectors)"
+    )]
+    internal: bool,
+    #[clap(
+        long,
+        conflicts_with_all = ["display", "group", "focused", "all", "internal"],
+        help = "Use the external displays"
+    )]
+    external: bool,
+}
+
+impl TargetArgs {
+    /// Displays selected by the user: the ones passed explicitly, the focused one, the
+    /// configured default or all of them
+    pub fn resolve(self, config: &Config) -> Result<Vec<Target>> {
+        if self.focused {
+            return Ok(vec![Target::from_name(focused_display(config)?)]);
+        }
+        if let Some(group) = self
+            .group
+            .iter()
+            .find(|group| !config.groups.contains_key(*group))
+        {
+            bail!("group {group} is not defined in the config");
+        }
+        if !self.display.is_empty() || !self.group.is_empty() {
+            return expand_groups(self.group.into_iter().chain(self.display).collect(), config);
+        }
+        if self.internal || self.external {
+            return Ok(all_displays(config)?
+                .into_iter()
+                .filter(|target| {
+                    target
+                        .info
+                        .as_ref()
+                        .is_some_and(|info| info.is_internal() == self.internal)
+                })
+                .collect());
+        }
+        match &config.default_display {
+            Some(default_display) if !self.all => {
+                expand_groups(vec![default_display.clone()], config)
+            }
+            _ => all_displays(config),
+        }
+    }
+}
+
+/// A display to operate on
+pub struct Target {
+    /// The name passed by the user or the connector name
+    pub name: String,
+    /// Information from the compositor, only available when operating on all displays
+    pub info: Option<DisplayInfo>,
+}
+
+impl Target {
+    fn from_name(name: String) -> Self {
+        Self { name, info: None }
+    }
+
+    pub fn brightness_control(&self, config: &Config) -> Result<BrightnessControl> {
+        let mut br_ctl = match &self.info {
+            Some(info) => BrightnessControl::for_device(&info.name, config)
+                .with_context(|| format!("unable to find brightness control for {}", info.name))?,
+            None => BrightnessControl::get_from_name(&self.name, config),
+        }?;
+        let display_config = self.display_config(config);
+        if let Some(ddc) = br_ctl.ddc_mut() {
+            if let Some(code) = config.brightness_vcp(display_config) {
+                ddc.brightness_vcp = code;
+            }
+            ddc.tuning = config.ddc_tuning(display_config);
+        }
+        Ok(br_ctl)
+    }
+
+    /// Directory of the drm connector of the display in /sys/class/drm
+    pub fn connector(&self, config: &Config) -> Result<PathBuf> {
+        let name = self.info.as_ref().map_or(&self.name, |info| &info.name);
+        connector_path(name)
+            .or_else(|| {
+                // The display might have been passed by model or description
+                let displays = DisplayInfo::get_displays(config).ok()?;
+                let display = displays.iter().find(|d| d.match_name(&self.name))?;
+                connector_path(&display.name)
+            })
+            .with_context(|| format!("no drm connector found for {}", self.name))
+    }
+
+    /// Options of this display in the config, if any
+    pub fn display_config<'a>(&self, config: &'a Config) -> Option<&'a DisplayConfig> {
+        match &self.info {
+            Some(info) => config.display(info),
+            None => config.display_by_name(&self.name),
+        }
+    }
+}
+
+fn connector_path(name: &str) -> Option<PathBuf> {
+    drm_connectors()
+        .ok()?
+        .into_iter()
+        .find(|path| connector_name(path) == name)
+}
+
+/// Whether the user selected exactly one display, in which case the output omits its name
+pub fn is_single(targets: &[Target]) -> bool {
+    matches!(targets, [target] if target.info.is_none())
+}
+
+/// Replace the names of the groups defined in the config with their members.
+/// Members can either be a display name, model or description, or `serial:` followed by
+/// the serial number of the display
+fn expand_groups(names: Vec<String>, config: &Config) -> Result<Vec<Target>> {
+    let mut displays = None;
+    let mut targets = Vec::new();
+    for name in names {
+        let Some(members) = config.groups.get(&name) else {
+            targets.push(Target::from_name(resolve_alias(name, config)));
+            continue;
+        };
+        for member in members {
+            let Some(serial) = member.strip_prefix("serial:") else {
+                targets.push(Target::from_name(resolve_alias(member.clone(), config)));
+                continue;
+            };
+            if displays.is_none() {
+                displays = Some(DisplayInfo::get_displays(config)?);
+            }
+            // A disconnected member will be reported as not found
+            let name = displays
+                .iter()
+                .flatten()
+                .find(|display| display.serial == serial)
+                .map_or(member, |display| &display.name);
+            targets.push(Target::from_name(name.clone()));
+        }
+    }
+    Ok(targets)
+}
+
+/// Replace the alias of a display with the name of its section in the config
+fn resolve_alias(name: String, config: &Config) -> String {
+    config
+        .display
+        .iter()
+        .find(|(_, display_config)| display_config.alias.as_ref() == Some(&name))
+        .map_or(name, |(display, _)| display.clone())
+}
+
+/// Run `op` on every target concurrently, since DDC operations can take hundreds of
+/// milliseconds each. The results are returned in the same order as the targets
+pub fn run_parallel<T: Send>(
+    targets: Vec<Target>,
+    op: impl Fn(Target) -> Result<T> + Sync,
+) -> Vec<(String, Result<T>)> {
+    let op = &op;
+    thread::scope(|scope| {
+        let handles = targets
+            .into_iter()
+            .map(|target| (target.name.clone(), scope.spawn(move || op(target))))
+            .collect::<Vec<_>>();
+        handles
+            .into_iter()
+            .map(|(name, handle)| {
+                let res = handle
+                    .join()
+                    .unwrap_or_else(|_| Err(eyre!("the operation on {name} panicked")));
+                (name, res)
+            })
+            .collect()
+    })
+}
+
+/// Displays affected when no display is passed, skipping the ignored ones
+fn all_displays(config: &Config) -> Result<Vec<Target>> {
+    Ok(DisplayInfo::get_displays(config)?
+        .into_iter()
+        .filter(|display| !config.display(display).is_some_and(|c| c.ignore))
+        .map(|display| Target {
+            name: display.name.clone(),
+            info: Some(display),
+        })
+        .collect())
+}
+
+/// Name of the display currently focused, as reported by the compositor, falling back to the
+/// primary one when no display has the focus
+fn focused_display(config: &Config) -> Result<String> {
+    let displays = DisplayInfo::get_displays(config)?;
+    displays
+        .iter()
+        .find(|display| display.focused)
+        .or_else(|| displays.iter().find(|display| display.primary))
+        .map(|display| display.name.clone())
+        .context("unable to find the focused display, this is only supported on Hyprland and sway")
+}